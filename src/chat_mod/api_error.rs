@@ -0,0 +1,249 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde_json::Value;
+
+/// 请求失败的类别，决定给用户的提示以及是否值得重试
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// API Key 无效或没有权限
+    Auth,
+    /// 请求过于频繁
+    RateLimit,
+    /// 对话超出模型的上下文长度
+    ContextLength,
+    /// 服务端错误或过载
+    Server,
+    /// 连接失败或传输中断
+    Network,
+    Other,
+}
+
+impl ErrorKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorKind::Auth => "认证失败",
+            ErrorKind::RateLimit => "请求过于频繁",
+            ErrorKind::ContextLength => "超出上下文长度",
+            ErrorKind::Server => "服务端错误",
+            ErrorKind::Network => "网络错误",
+            ErrorKind::Other => "请求失败",
+        }
+    }
+
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            ErrorKind::Auth => Some("请在模型配置中检查 API Key 和 URL"),
+            ErrorKind::RateLimit => Some("请稍后重试，或降低请求频率"),
+            ErrorKind::ContextLength => Some("对话过长，可使用 :revert 撤销部分对话或 :new 开始新会话后重试"),
+            ErrorKind::Server => Some("服务暂时不可用，请稍后重试"),
+            ErrorKind::Network => Some("请检查网络连接和模型 URL"),
+            ErrorKind::Other => None,
+        }
+    }
+}
+
+/// 接口返回的错误，解析自响应体或流中的错误事件
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub kind: ErrorKind,
+    /// HTTP 状态码，流中途出错或网络错误时为 None
+    pub status: Option<u16>,
+    pub message: String,
+    pub error_type: Option<String>,
+    pub code: Option<String>,
+    /// 响应头 Retry-After 给出的等待时间
+    pub retry_after: Option<Duration>,
+}
+
+// 错误信息中用于识别上下文超长的关键字
+const CONTEXT_PATTERNS: &[&str] = &[
+    "context_length", "context length", "maximum context", "context window", "too many tokens", "prompt is too long",
+];
+
+impl ApiError {
+    /// 解析非 2xx 响应的响应体
+    pub fn from_response(status: u16, body: &str) -> ApiError {
+        ApiError::parse(Some(status), body)
+    }
+
+    /// 解析 `event: error` 事件的数据
+    pub fn from_error_event(data: &str) -> ApiError {
+        ApiError::parse(None, data)
+    }
+
+    fn parse(status: Option<u16>, body: &str) -> ApiError {
+        match serde_json::from_str::<Value>(body) {
+            Ok(value) => ApiError::from_payload(status, &value),
+            Err(_) => {
+                let body = body.trim();
+                let message = if body.is_empty() {
+                    String::from("服务端未返回错误信息")
+                } else {
+                    truncate(body, 300)
+                };
+                ApiError::classified(status, message, None, None)
+            }
+        }
+    }
+
+    /// 流中以 JSON 形式返回的错误，如 `{"error": {...}}`；不是错误时返回 None，
+    /// 有些服务端在正常的数据块中也带有 `"error": null`
+    pub fn from_stream_data(data: &str) -> Option<ApiError> {
+        let value = serde_json::from_str::<Value>(data).ok()?;
+        if value.get("error").is_none_or(Value::is_null) {
+            return None;
+        }
+        Some(ApiError::from_payload(None, &value))
+    }
+
+    /// 兼容常见的几种格式：`{"error": {"message", "type", "code"}}`、`{"error": "..."}`、
+    /// `{"message": "..."}` 和 `{"detail": "..."}`
+    fn from_payload(status: Option<u16>, value: &Value) -> ApiError {
+        let error = value.get("error").unwrap_or(value);
+        let text = |v: Option<&Value>| match v {
+            Some(Value::String(s)) if !s.is_empty() => Some(s.clone()),
+            Some(Value::Number(n)) => Some(n.to_string()),
+            _ => None,
+        };
+        let message = text(error.get("message"))
+            .or_else(|| text(Some(error)))
+            .or_else(|| text(value.get("message")))
+            .or_else(|| text(value.get("detail")))
+            .unwrap_or_else(|| truncate(&value.to_string(), 300));
+        ApiError::classified(status, message, text(error.get("type")), text(error.get("code")))
+    }
+
+    pub fn network(message: impl fmt::Display) -> ApiError {
+        ApiError {
+            kind: ErrorKind::Network,
+            status: None,
+            message: message.to_string(),
+            error_type: None,
+            code: None,
+            retry_after: None,
+        }
+    }
+
+    fn classified(status: Option<u16>, message: String, error_type: Option<String>, code: Option<String>) -> ApiError {
+        let kind = classify(status, &message, error_type.as_deref(), code.as_deref());
+        ApiError { kind, status, message, error_type, code, retry_after: None }
+    }
+
+    /// 限流、服务端错误和网络错误可能在稍后自行恢复，值得自动重试
+    pub fn is_transient(&self) -> bool {
+        matches!(self.kind, ErrorKind::RateLimit | ErrorKind::Server | ErrorKind::Network)
+    }
+
+    /// 输出错误及处理建议
+    pub fn report(&self) {
+        eprintln!("❌ {}", self);
+        if let Some(hint) = self.kind.hint() {
+            eprintln!("💡 {}", hint);
+        }
+    }
+}
+
+fn classify(status: Option<u16>, message: &str, error_type: Option<&str>, code: Option<&str>) -> ErrorKind {
+    let tags = format!("{} {}", error_type.unwrap_or_default(), code.unwrap_or_default()).to_lowercase();
+    let message = message.to_lowercase();
+    if CONTEXT_PATTERNS.iter().any(|p| tags.contains(p) || message.contains(p)) {
+        ErrorKind::ContextLength
+    } else if matches!(status, Some(401 | 403)) || tags.contains("auth") || tags.contains("api_key") || tags.contains("permission") {
+        ErrorKind::Auth
+    } else if status == Some(429) || tags.contains("rate_limit") {
+        ErrorKind::RateLimit
+    } else if status.is_some_and(|s| s >= 500) || tags.contains("server_error") || tags.contains("overloaded") {
+        ErrorKind::Server
+    } else {
+        ErrorKind::Other
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind.label())?;
+        if let Some(status) = self.status {
+            write!(f, "（状态码 {}）", status)?;
+        }
+        write!(f, ": {}", self.message)?;
+        let details: Vec<String> = [("类型", &self.error_type), ("代码", &self.code)].iter()
+            .filter_map(|(label, value)| value.as_ref().map(|v| format!("{} {}", label, v)))
+            .collect();
+        if !details.is_empty() {
+            write!(f, " [{}]", details.join("，"))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_by_status() {
+        let body = r#"{"error": {"message": "Invalid API key", "type": "invalid_request_error", "code": "invalid_api_key"}}"#;
+        let error = ApiError::from_response(401, body);
+        assert_eq!(error.kind, ErrorKind::Auth);
+        assert_eq!(error.message, "Invalid API key");
+        assert_eq!(error.code.as_deref(), Some("invalid_api_key"));
+        assert_eq!(ApiError::from_response(429, "{}").kind, ErrorKind::RateLimit);
+        assert_eq!(ApiError::from_response(503, "").kind, ErrorKind::Server);
+        assert_eq!(ApiError::from_response(404, r#"{"detail": "Not Found"}"#).kind, ErrorKind::Other);
+    }
+
+    #[test]
+    fn context_length_wins_over_status() {
+        let body = r#"{"error": {"message": "This model's maximum context length is 8192 tokens", "code": "context_length_exceeded"}}"#;
+        assert_eq!(ApiError::from_response(400, body).kind, ErrorKind::ContextLength);
+        assert_eq!(ApiError::from_response(413, r#"{"message": "prompt is too long"}"#).kind, ErrorKind::ContextLength);
+    }
+
+    #[test]
+    fn non_json_body_is_kept_as_message() {
+        let error = ApiError::from_response(502, "<html>Bad Gateway</html>");
+        assert_eq!(error.kind, ErrorKind::Server);
+        assert_eq!(error.message, "<html>Bad Gateway</html>");
+        assert_eq!(ApiError::from_response(500, "  ").message, "服务端未返回错误信息");
+    }
+
+    #[test]
+    fn error_event_without_status() {
+        let error = ApiError::from_error_event(r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#);
+        assert_eq!(error.kind, ErrorKind::Server);
+        assert_eq!(error.status, None);
+        assert!(error.is_transient());
+    }
+
+    #[test]
+    fn stream_data_with_error() {
+        let error = ApiError::from_stream_data(r#"{"error": {"message": "Internal error", "type": "server_error"}}"#).unwrap();
+        assert_eq!(error.kind, ErrorKind::Server);
+        let error = ApiError::from_stream_data(r#"{"error": "rate_limit_exceeded"}"#).unwrap();
+        assert_eq!(error.message, "rate_limit_exceeded");
+    }
+
+    #[test]
+    fn stream_data_without_error() {
+        let chunk = r#"{"choices": [{"delta": {"content": "hi"}}], "error": null}"#;
+        assert!(ApiError::from_stream_data(chunk).is_none());
+        assert!(ApiError::from_stream_data(r#"{"choices": []}"#).is_none());
+        assert!(ApiError::from_stream_data("[DONE]").is_none());
+    }
+
+    #[test]
+    fn display_includes_details() {
+        let error = ApiError::from_response(429, r#"{"error": {"message": "slow down", "type": "requests", "code": "rate_limit_exceeded"}}"#);
+        assert_eq!(error.to_string(), "请求过于频繁（状态码 429）: slow down [类型 requests，代码 rate_limit_exceeded]");
+    }
+}
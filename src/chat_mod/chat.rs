@@ -5,13 +5,21 @@ use crate::chat_mod::model::ModelList;
 use crate::chat_mod::prompt::prompt;
 use crate::chat_mod::model::model_management;
 use crate::chat_mod::model::Model;
+use crate::chat_mod::api_error::ApiError;
+use crate::chat_mod::retry::{countdown, parse_retry_after, RetryPolicy};
+use crate::chat_mod::session::{self, Session};
+use crate::chat_mod::sse::{Event, SseDecoder};
+use crate::chat_mod::transcript::{Transcript, TranscriptFormat};
+use crate::storage_mod::data_file::{data_path, is_conflict, write_atomic};
 use reqwest::Client;
 use futures::StreamExt;
-use std::fs::File;
-use std::path::Path;
+use std::path::PathBuf;
 
 
 
+/// `:save` 只给出文件名时，导出的对话保存在数据目录下的这个子目录中
+const HISTORY_DIR: &str = if cfg!(windows) { "History" } else { "history" };
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct Message {
     pub role: String,
@@ -24,27 +32,6 @@ pub struct RequestBody {
     stream: bool
 }
 
-#[derive(Default, Debug, Clone, Deserialize, Serialize)]
-struct Usage {
-    completion_tokens: u32,
-    prompt_tokens: u32,
-    total_tokens: u32,
-}
-
-#[derive(Default, Debug, Clone, Deserialize, Serialize)]
-struct Choice {
-    message: Message,
-    finish_reason: String,
-    index: u32,
-}
-
-#[derive(Default, Debug, Clone, Deserialize, Serialize)]
-struct ChatResponse {
-    model: String,
-    choices: Vec<Choice>,
-    usage: Usage,
-}
-
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 struct ChatResponseChunk {
     model: String,
@@ -67,11 +54,20 @@ struct MessageDelta {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct App {
     pub assistant_name: String,
-    model: Model,
-    models: ModelList,
-    pub request_body: RequestBody
+    pub model: Model,
+    pub models: ModelList,
+    pub request_body: RequestBody,
+    /// 当前对话对应的会话，消息以 request_body 中的为准，保存时同步
+    session: Session,
+    /// 上次请求失败、尚未得到回复的提问，可通过 :retry 重新发送
+    pending: Option<String>,
+    /// 暂时性错误的自动重试策略，由环境变量配置，不随应用状态序列化
+    #[serde(skip, default = "RetryPolicy::from_env")]
+    pub retry: RetryPolicy,
 }
 
+// 菜单项沿用全大写的命名
+#[allow(clippy::upper_case_acronyms)]
 enum Menu {
     MODEL,
     PROMPT,
@@ -80,7 +76,7 @@ enum Menu {
 }
 
 impl Menu {
-    fn form_handler(str: &String) -> Self{
+    fn form_handler(str: &str) -> Self{
         match str.trim().to_lowercase().as_str() {
             "1"|"model" => Menu::MODEL,
             "2"|"prompt" => Menu::PROMPT,
@@ -94,7 +90,11 @@ impl Menu {
 impl Default for App {
     fn default() -> Self {
         let mut model = Model::default();
-        let models = ModelList::load_from_file();
+        // 模型配置损坏时提示后使用环境变量中的默认配置，不改动文件
+        let models = ModelList::load_from_file().unwrap_or_else(|e| {
+            eprintln!("❌ 读取模型配置失败: {}", e);
+            ModelList::new()
+        });
         if models.models.is_empty() {
             model.model_name = String::from("deepseek-chat");
             model.url = std::env::var("CHAT_URL").unwrap_or_default();
@@ -105,81 +105,119 @@ impl Default for App {
                 .cloned()
                 .unwrap_or_default();
         }
-        let mut request_body = RequestBody::default();
-        request_body.stream = true;
+        let request_body = RequestBody {
+            stream: true,
+            ..Default::default()
+        };
         Self { 
             assistant_name : String::from("user"),
             model,
             models,
-            request_body
+            request_body,
+            session: Session::new(),
+            pending: None,
+            retry: RetryPolicy::from_env(),
         }
     }
 }
 
 impl App {
-    fn save(&self, file_name: &String) -> Result<(), std::io::Error> {
-
-        if self.request_body.messages.is_empty() {
-            return Ok(());
+    /// 保存当前会话，还没有提问时不保存
+    fn save_session(&mut self) {
+        if !self.request_body.messages.iter().any(|m| m.role == "user") {
+            return;
         }
-
-        let path = if cfg!(windows) {
-            // Windows系统使用AppData目录
-            dirs::data_local_dir().map(|mut p| {
-                p.push("SmallTool");
-                p.push("History");
-                p.push(format!("{}.md", file_name));
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取AppData目录，则使用当前目录
-                Path::new(format!("{}.md", file_name).as_str()).to_path_buf()
-            })
-        } else {
-            // 非Windows系统保持原逻辑
-            dirs::data_dir().map(|mut p| {
-                p.push("small_tools");
-                p.push("history");
-                p.push(format!("{}.md", file_name));
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取数据目录，则使用当前目录
-                Path::new(format!("{}.md", file_name).as_str()).to_path_buf()
-            })
-        };
-        
-        // 确保目录存在
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+        self.session.messages = self.request_body.messages.clone();
+        self.session.model = self.model.model_name.clone();
+        self.session.prompt = (self.assistant_name != "user").then(|| self.assistant_name.clone());
+        match self.session.save() {
+            Ok(()) => {},
+            Err(e) if is_conflict(&e) => {
+                let old = self.session.id.clone();
+                match self.session.fork() {
+                    Ok(()) => println!("⚠️ 会话 {} 已在其他窗口中更新，当前对话已另存为新会话 {}", old, self.session.id),
+                    Err(e) => eprintln!("❌ 保存会话失败: {}", e),
+                }
+            },
+            Err(e) => eprintln!("❌ 保存会话失败: {}", e),
         }
-        
-        let mut file = File::create(path)?;
-
-        let mut mh = String::new();
+    }
 
-        let mut count = 0;
+    /// 开始新会话，已有的会话保留在会话列表中
+    pub fn new_session(&mut self) {
+        self.session = Session::new();
+        self.request_body.messages.clear();
+    }
 
-        for message in &self.request_body.messages {
-            if count == 0  {
-                count+=1;
-                continue;
-            }
-            if count%2 == 1 {
-                count+=1;
-                mh += &format!("{}:\n{}\n", "用户", message.content);
-            }
-            if count%2 == 0 {
-                count+=1;
-                mh += &format!("{}\n{}\n", self.assistant_name, message.content);
+    // 切换到已保存的会话，恢复其消息、Prompt 角色和模型
+    fn resume_session(&mut self, session: Session) {
+        self.request_body.messages = session.messages.clone();
+        self.assistant_name = session.prompt.clone().unwrap_or_else(|| String::from("user"));
+        if session.model != self.model.model_name {
+            match self.models.models.iter().find(|m| m.model_name == session.model) {
+                Some(model) => self.model = model.clone(),
+                None => println!("⚠️ 未找到会话使用的模型 '{}'，继续使用 {}", session.model, self.model.model_name),
             }
         }
-        file.write_all(mh.as_bytes())?;
-        file.flush()?;
+        self.session = session;
+    }
 
-        Ok(())
+    /// 导出当前对话，`args` 为 `<文件名> [md|json|html]`；未指定格式时按扩展名推断，默认 Markdown，
+    /// 只给出文件名时保存到数据目录的 history 子目录
+    fn save_transcript(&self, args: &str) -> Result<PathBuf, String> {
+        if self.request_body.messages.is_empty() {
+            return Err(String::from("当前没有可保存的对话"));
+        }
+        let mut words: Vec<&str> = args.split_whitespace().collect();
+        let explicit = match words.as_slice() {
+            [.., last] if words.len() > 1 => TranscriptFormat::parse(last),
+            _ => None,
+        };
+        if explicit.is_some() {
+            words.pop();
+        }
+        let mut name = words.join(" ");
+        if name.is_empty() {
+            name = self.session.id.clone();
+        }
+        let format = explicit
+            .or_else(|| TranscriptFormat::from_path(&name))
+            .unwrap_or(TranscriptFormat::Markdown);
+        if TranscriptFormat::from_path(&name) != Some(format) {
+            name = format!("{}.{}", name, format.extension());
+        }
+
+        let prompt = (self.assistant_name != "user").then_some(self.assistant_name.as_str());
+        // 会话只在每轮对话后同步，标题按当前的消息计算
+        let mut synced = self.session.clone();
+        synced.messages = self.request_body.messages.clone();
+        synced.prompt = prompt.map(str::to_string);
+        let transcript = Transcript {
+            title: synced.display_title(),
+            model: &self.model.model_name,
+            prompt,
+            messages: &self.request_body.messages,
+        };
+        let content = format.render(&transcript)?;
+
+        let path = PathBuf::from(&name);
+        let path = if path.components().count() > 1 {
+            path
+        } else {
+            data_path(HISTORY_DIR).join(path)
+        };
+        write_atomic(&path, content.as_bytes()).map_err(|e| e.to_string())?;
+        Ok(path)
     }
 
 }
 
+// 输入以 `name` 开头且其后为空白或结束时返回其余部分，避免 `:renamed` 被当作 `:rename`
+fn command_args<'a>(input: &'a str, name: &str) -> Option<&'a str> {
+    let rest = input.strip_prefix(name)?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then(|| rest.trim())
+}
+
 fn chat(app: &mut App) -> bool{
     // app.request_body.model = String::from("deepseek-chat");
 
@@ -202,7 +240,65 @@ fn chat(app: &mut App) -> bool{
     }
 
     if sm.eq(":c") {
-        app.request_body.messages.clear();
+        app.new_session();
+        return true;
+    }
+
+    if sm.eq(":new") {
+        // 保留已选择的 Prompt
+        let prompt = (app.assistant_name != "user")
+            .then(|| app.request_body.messages.iter().find(|m| m.role == "system").cloned())
+            .flatten();
+        app.new_session();
+        app.request_body.messages.extend(prompt);
+        println!("🆕 已开始新会话");
+        return true;
+    }
+
+    if sm.eq(":sessions") {
+        match session::list() {
+            Ok(sessions) if sessions.is_empty() => println!("暂无保存的会话"),
+            Ok(sessions) => {
+                for (i, s) in sessions.iter().enumerate() {
+                    s.show(i, s.id == app.session.id);
+                }
+                println!("使用 :resume <ID、ID前缀或序号> 继续某个会话");
+            },
+            Err(e) => eprintln!("❌ 读取会话列表失败: {}", e),
+        }
+        return true;
+    }
+
+    if let Some(key) = command_args(&sm, ":resume") {
+        if key.is_empty() {
+            eprintln!("⚠️ 用法: :resume <ID、ID前缀或序号>，可通过 :sessions 查看");
+            return true;
+        }
+        match session::find(key) {
+            Ok(Some(session)) => {
+                println!("📂 已切换到会话 {}: {}（{} 条消息）", session.id, session.display_title(), session.message_count());
+                // 显示最后一轮对话，方便接着聊
+                if let Some(last) = session.messages.iter().rposition(|m| m.role == "user") {
+                    for message in &session.messages[last..] {
+                        println!("[{}] {}", message.role, message.content);
+                    }
+                }
+                app.resume_session(session);
+            },
+            Ok(None) => eprintln!("❌ 未找到会话: {}", key),
+            Err(e) => eprintln!("❌ 读取会话失败: {}", e),
+        }
+        return true;
+    }
+
+    if let Some(title) = command_args(&sm, ":rename") {
+        if title.is_empty() {
+            eprintln!("⚠️ 用法: :rename <标题>");
+            return true;
+        }
+        app.session.title = title.to_string();
+        app.save_session();
+        println!("✏️ 会话已重命名为: {}", title);
         return true;
     }
     
@@ -218,123 +314,190 @@ fn chat(app: &mut App) -> bool{
         }
         app.request_body.messages.pop();
         app.request_body.messages.pop();
+        app.save_session();
         return true;
     }
 
-    if sm.starts_with(":save:") {
-        // let file_name = sm.trim_start_matches(":save:").trim().to_string();
-        // app.save(&file_name).expect("保存失败");
+    // 兼容旧的 `:save:<文件名>` 写法
+    if let Some(args) = command_args(&sm, ":save").or_else(|| sm.strip_prefix(":save:")) {
+        match app.save_transcript(args) {
+            Ok(path) => println!("✅ 对话已保存到 {}", path.display()),
+            Err(e) => eprintln!("❌ 保存失败: {}", e),
+        }
         return true;
     }
 
+    if sm.eq(":retry") {
+        match app.pending.take() {
+            Some(pending) => sm = pending,
+            None => {
+                eprintln!("⚠️ 没有等待重试的消息");
+                return true;
+            }
+        }
+    }
+
     // 将用户消息添加到请求体中
+    app.pending = None;
     app.request_body.messages.push(Message {
         role: String::from("user"),
         content: sm,
     });
     app.request_body.stream = true; // 启用流式输出
 
-    // 创建要发送的JSON数据
-    let json_data = json!({
+    // 使用异步运行时执行流式请求
+    let json_data = build_request(app);
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let result = rt.block_on(async {
+        stream_chat(app, json_data, true).await
+    });
+    match result {
+        Ok(()) => app.save_session(),
+        Err(e) => {
+            // 撤回没有得到回复的提问，保留下来供重试，避免历史中出现连续两条提问
+            app.pending = app.request_body.messages.pop().map(|m| m.content);
+            e.report();
+            println!("🔁 输入 :retry 重新发送这条消息");
+        }
+    }
+    true
+}
+
+// 创建要发送的JSON数据
+pub fn build_request(app: &App) -> serde_json::Value {
+    json!({
         "model": &app.model.model_name,
         "messages": &app.request_body.messages,
         "stream": &app.request_body.stream
-    });
-
-    // 使用异步运行时执行流式请求
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(async {
-        stream_chat(app, json_data).await
     })
 }
 
-async fn stream_chat(app: &mut App, json_data: serde_json::Value) -> bool {
+// decorate 为 false 时只输出回复正文，便于在管道中使用；
+// 失败时不修改消息历史，已输出的部分回复也不会记录。
+// 暂时性的错误在还没有输出任何内容时按 app.retry 自动重试
+pub async fn stream_chat(app: &mut App, json_data: serde_json::Value, decorate: bool) -> Result<(), ApiError> {
+    let mut attempt = 0;
+    loop {
+        let mut full_content = String::new();
+        let error = match request_once(app, &json_data, decorate, &mut full_content).await {
+            Ok(()) => {
+                // 将助手的回复添加到消息历史中
+                app.request_body.messages.push(Message {
+                    role: "assistant".to_string(),
+                    content: full_content,
+                });
+                return Ok(());
+            },
+            Err(error) => error,
+        };
+        // 已经输出了部分回复时重试会重复内容，不自动重试；交互模式中这条提问仍可通过 :retry 重新发送
+        if !full_content.is_empty() {
+            eprintln!("⚠️ 回复在输出过程中中断，已输出的部分不会保存");
+            return Err(error);
+        }
+        if !error.is_transient() {
+            return Err(error);
+        }
+        let Some(delay) = app.retry.delay(attempt, error.retry_after) else {
+            return Err(error);
+        };
+        attempt += 1;
+        eprintln!("⚠️ {}", error);
+        if !countdown(delay, attempt, app.retry.max_retries).await {
+            return Err(error);
+        }
+    }
+}
+
+async fn request_once(app: &App, json_data: &serde_json::Value, decorate: bool, full_content: &mut String) -> Result<(), ApiError> {
     let client = Client::new();
     
     // 发送包含请求体的POST请求
-    let response = match client
+    let response = client
         .post(&app.model.url)
         .header("Content-Type", "application/json")
         .header("Authorization", format!("Bearer {}", app.model.api_key))
-        .json(&json_data)
+        .json(json_data)
         .send()
         .await
-    {
-        Ok(response) => response,
-        Err(e) => {
-            eprintln!("❌ 发送请求失败: {}", e);
-            return false;
-        }
-    };
-
-    if !response.status().is_success() {
-        eprintln!("❌ 请求失败，状态码: {}", response.status());
-        return false;
+        .map_err(|e| ApiError::network(format!("发送请求失败: {}", e)))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let retry_after = response.headers().get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_retry_after(v, chrono::Utc::now()));
+        let body = response.text().await.unwrap_or_default();
+        let mut error = ApiError::from_response(status.as_u16(), &body);
+        error.retry_after = retry_after;
+        return Err(error);
     }
 
     let mut stream = response.bytes_stream();
-    let mut full_content = String::new();
     let role = if !app.assistant_name.eq("user") {
         app.assistant_name.clone()     
     } else {
         String::from("🤖 Assistant")
     };
 
-    println!("================================================================================");
-    println!("👤 角色: {}", role);
-    println!("--------------------------------------------------------------------------------");
-    print!("💬 回复: ");
+    if decorate {
+        println!("================================================================================");
+        println!("👤 角色: {}", role);
+        println!("--------------------------------------------------------------------------------");
+        print!("💬 回复: ");
+    }
     
-    // 实时处理流式响应
-    while let Some(chunk) = stream.next().await {
-        match chunk {
-            Ok(bytes) => {
-                let chunk_str = String::from_utf8_lossy(&bytes);
-                let lines: Vec<&str> = chunk_str.split('\n').collect();
-                
-                for line in lines {
-                    if line.starts_with("data: ") {
-                        let data = &line[6..]; // 移除 "data: " 前缀
-                        
-                        if data == "[DONE]" {
-                            // 流完成
-                            break;
-                        }
-                        
-                        // 解析JSON数据
-                        match serde_json::from_str::<ChatResponseChunk>(data) {
-                             Ok(chunk_data) => {
-                                if let Some(choice) = chunk_data.choices.first() {
-                                    if let Some(ref delta) = choice.delta.content {
-                                        print!("{}", delta);
-                                        std::io::stdout().flush().unwrap(); // 立即刷新输出
-                                        full_content.push_str(delta);
-                                    }
-                                }
-                            }
-                            Err(_) => {
-                                // 忽略解析错误，可能是一些特殊格式的数据
-                            }
-                        }
-                    }
+    // 实时处理流式响应，事件可能跨越多个网络分块，由解码器负责拼接
+    let mut decoder = SseDecoder::new();
+    let result = async {
+        while let Some(chunk) = stream.next().await {
+            let bytes = chunk.map_err(|e| ApiError::network(format!("接收数据时出错: {}", e)))?;
+            for event in decoder.feed(&bytes) {
+                if handle_event(&event, full_content)? {
+                    return Ok(());
                 }
             }
-            Err(e) => {
-                eprintln!("❌ 接收数据时出错: {}", e);
-                return false;
-            }
         }
+        match decoder.finish() {
+            Some(event) => handle_event(&event, full_content).map(|_| ()),
+            None => Ok(()),
+        }
+    }.await;
+
+    if decorate {
+        println!("\n================================================================================");
+    } else if result.is_ok() || !full_content.is_empty() {
+        // 没有输出任何内容就失败时不留空行，重试的输出保持紧凑
+        println!();
     }
-    
-    println!("\n================================================================================");
-    
-    // 将助手的回复添加到消息历史中
-    app.request_body.messages.push(Message { 
-        role: "assistant".to_string(), 
-        content: full_content.clone() 
-    });
-    
-    true
+    result
+}
+
+// 处理一个事件并输出增量内容，流结束时返回 true；服务端在流中返回错误时返回 Err
+fn handle_event(event: &Event, full_content: &mut String) -> Result<bool, ApiError> {
+    match event.event.as_deref() {
+        None | Some("message") => {},
+        Some("error") => {
+            return Err(ApiError::from_error_event(&event.data));
+        },
+        // 其他类型的事件与回复内容无关
+        Some(_) => return Ok(false),
+    }
+    if event.is_done() {
+        return Ok(true);
+    }
+    if let Some(error) = ApiError::from_stream_data(&event.data) {
+        return Err(error);
+    }
+    // 无法解析的数据可能是一些特殊格式的内容，忽略
+    if let Ok(chunk_data) = serde_json::from_str::<ChatResponseChunk>(&event.data)
+        && let Some(choice) = chunk_data.choices.first()
+        && let Some(ref delta) = choice.delta.content {
+        print!("{}", delta);
+        std::io::stdout().flush().unwrap(); // 立即刷新输出
+        full_content.push_str(delta);
+    }
+    Ok(false)
 }
 
 pub fn chat_run() {
@@ -371,7 +534,7 @@ pub fn chat_run() {
                 prompt(&mut app);
             },
             Menu::CHAT => {
-                println!("💬 进入问答模式（“:b”退出）");
+                println!("💬 进入问答模式（“:b”退出，“:sessions”查看保存的会话）");
                 loop {
                     if !chat(&mut app) {
                         break;
@@ -389,4 +552,4 @@ pub fn chat_run() {
         println!("按回车键继续...");
         let _ = stdin().read_line(&mut String::new());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,115 @@
+use std::io::Read;
+use crate::chat_mod::chat::{build_request, stream_chat, App, Message};
+use crate::chat_mod::session;
+use crate::cli_mod::args::{usage_error, Args, EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+
+const CHAT_USAGE: &str = "\
+用法: small_tools chat <子命令> [选项]
+
+子命令:
+  ask [问题] [--model <模型名称>] [--retries <次数>]
+                                   单次提问，回复直接输出到标准输出
+                                   未给出问题或问题为 \"-\" 时从标准输入读取
+                                   --retries 覆盖暂时性错误的自动重试次数，0 表示不重试
+  sessions                         列出保存的会话，可在问答模式中用 :resume <ID、ID前缀或序号> 继续";
+
+/// 非交互式的问答命令入口，返回进程退出码
+pub fn chat_cmd(args: &[String]) -> i32 {
+    let Some((sub, rest)) = args.split_first() else {
+        eprintln!("{}", CHAT_USAGE);
+        return EXIT_USAGE;
+    };
+    match sub.as_str() {
+        "ask" => ask(rest),
+        "sessions" => sessions(rest),
+        "help" | "-h" | "--help" => {
+            println!("{}", CHAT_USAGE);
+            EXIT_OK
+        },
+        _ => usage_error(&format!("未知的子命令: {}", sub), CHAT_USAGE),
+    }
+}
+
+fn ask(args: &[String]) -> i32 {
+    let args = match Args::parse(args, &["model", "retries"], &[]) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e, CHAT_USAGE),
+    };
+
+    let mut question = args.positionals().join(" ");
+    if question.is_empty() || question == "-" {
+        question.clear();
+        if let Err(e) = std::io::stdin().read_to_string(&mut question) {
+            eprintln!("❌ 读取标准输入失败: {}", e);
+            return EXIT_FAILURE;
+        }
+    }
+    let question = question.trim();
+    if question.is_empty() {
+        eprintln!("⚠️ 输入内容不能为空");
+        return EXIT_USAGE;
+    }
+
+    let mut app = App::default();
+    if let Some(name) = args.opt("model") {
+        match app.models.models.iter().find(|m| m.model_name == name) {
+            Some(model) => app.model = model.clone(),
+            None => {
+                eprintln!("❌ 未找到名称为 '{}' 的模型", name);
+                return EXIT_FAILURE;
+            }
+        }
+    }
+    if let Some(retries) = args.opt("retries") {
+        match retries.trim().parse() {
+            Ok(retries) => app.retry.max_retries = retries,
+            Err(_) => {
+                eprintln!("❌ 无效的重试次数: {}", retries);
+                return EXIT_USAGE;
+            }
+        }
+    }
+    if app.model.url.is_empty() {
+        eprintln!("❌ 未配置模型 URL，请先在模型配置中添加模型或设置 CHAT_URL");
+        return EXIT_FAILURE;
+    }
+
+    app.request_body.messages.push(Message {
+        role: String::from("user"),
+        content: question.to_string(),
+    });
+
+    let json_data = build_request(&app);
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let result = rt.block_on(async {
+        stream_chat(&mut app, json_data, false).await
+    });
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            e.report();
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn sessions(args: &[String]) -> i32 {
+    if let Some(arg) = args.first() {
+        return usage_error(&format!("未知参数: {}", arg), CHAT_USAGE);
+    }
+    match session::list() {
+        Ok(sessions) => {
+            if sessions.is_empty() {
+                println!("暂无保存的会话");
+            }
+            for (i, s) in sessions.iter().enumerate() {
+                s.show(i, false);
+            }
+            EXIT_OK
+        },
+        Err(e) => {
+            eprintln!("❌ 读取会话列表失败: {}", e);
+            EXIT_FAILURE
+        }
+    }
+}
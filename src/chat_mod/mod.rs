@@ -1,3 +1,9 @@
+pub mod api_error;
 pub mod chat;
+pub mod chat_cmd;
 pub mod prompt;
-pub mod model;
\ No newline at end of file
+pub mod model;
+pub mod retry;
+pub mod session;
+pub mod sse;
+pub mod transcript;
@@ -1,8 +1,9 @@
 use std::io::stdin;
-use std::fs;
-use std::path::Path;
 use serde::{Deserialize, Serialize};
 
+use crate::storage_mod::data_file::{is_conflict, resolve_conflict, DataFile};
+use crate::storage_mod::schema::{self, Schema};
+
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct Model {
     pub api_key: String,
@@ -11,89 +12,56 @@ pub struct Model {
     pub default: bool,
 }
 
+const MODELS_FILE: &str = "models.json";
+/// models.json 的版本：0 为 `{"models": [...]}`，1 起数组包在 `{"version", "data"}` 中
+const SCHEMA: Schema = Schema { migrations: &[|data| schema::unwrap_field(data, "models")] };
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ModelList {
     pub models: Vec<Model>,
+    #[serde(skip)]
+    file: DataFile,
+}
+
+impl Default for ModelList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ModelList {
     pub fn new() -> Self {
         Self {
             models: Vec::new(),
+            file: DataFile::new(MODELS_FILE, SCHEMA),
         }
     }
 
-    pub fn load_from_file() -> Self {
-        let path = if cfg!(windows) {
-            // Windows系统使用AppData目录
-            dirs::data_local_dir().map(|mut p| {
-                p.push("SmallTool");
-                p.push("models.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取AppData目录，则使用当前目录
-                Path::new("models.json").to_path_buf()
-            })
-        } else {
-            // 非Windows系统保持原逻辑
-            dirs::data_dir().map(|mut p| {
-                p.push("small_tools");
-                p.push("models.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取数据目录，则使用当前目录
-                Path::new("models.json").to_path_buf()
-            })
-        };
-        
-        if path.exists() {
-            match fs::read_to_string(path) {
-                Ok(content) => {
-                    match serde_json::from_str(&content) {
-                        Ok(models) => models,
-                        Err(_) => ModelList::new(),
-                    }
-                }
-                Err(_) => ModelList::new(),
-            }
-        } else {
-            ModelList::new()
-        }
+    /// 文件不存在时返回空列表，无法解析时返回带行列号的错误而不是静默地当作空列表
+    pub fn load_from_file() -> Result<Self, std::io::Error> {
+        let mut list = ModelList::new();
+        list.reload()?;
+        Ok(list)
     }
 
-    pub fn save_to_file(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let path = if cfg!(windows) {
-            // Windows系统使用AppData目录
-            dirs::data_local_dir().map(|mut p| {
-                p.push("SmallTool");
-                p.push("models.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取AppData目录，则使用当前目录
-                Path::new("models.json").to_path_buf()
-            })
-        } else {
-            // 非Windows系统保持原逻辑
-            dirs::data_dir().map(|mut p| {
-                p.push("small_tools");
-                p.push("models.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取数据目录，则使用当前目录
-                Path::new("models.json").to_path_buf()
-            })
-        };
-        
-        // 确保目录存在
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        
-        let json = serde_json::to_string_pretty(self)?;
-        fs::write(path, json)?;
+    /// 通过同一个数据文件重新读取，修改期间持有的锁仍然有效
+    pub fn reload(&mut self) -> Result<(), std::io::Error> {
+        self.models = self.file.read_json()?.unwrap_or_default();
         Ok(())
     }
 
+    /// 交互菜单中加载，无法解析时询问是否备份损坏的文件后以空列表继续，放弃时返回 None
+    pub fn load_or_recover() -> Option<Self> {
+        let mut list = ModelList::new();
+        list.models = list.file.read_or_recover()?;
+        Some(list)
+    }
+
+    /// 文件在读取后被其他实例修改过时返回冲突错误而不覆盖，`force` 为 true 时直接覆盖
+    pub fn save_to_file(&mut self, force: bool) -> Result<(), std::io::Error> {
+        self.file.write_json(&self.models, force)
+    }
+
     pub fn add_model(&mut self, model: Model) {
         self.models.push(model);
     }
@@ -159,6 +127,8 @@ impl ModelList {
     }
 }
 
+// 菜单项沿用全大写的命名
+#[allow(clippy::upper_case_acronyms)]
 enum Menu {
     ADD,
     EDIT,
@@ -169,7 +139,7 @@ enum Menu {
 }
 
 impl Menu {
-    fn form_handler(str: &String) -> Self{
+    fn form_handler(str: &str) -> Self{
         match str.trim().to_lowercase().as_str() {
             "1"|"add" => Menu::ADD,
             "2"|"edit" => Menu::EDIT,
@@ -184,9 +154,11 @@ impl Menu {
 }
 
 pub fn model_management() -> Option<Model> {
-    let mut models = ModelList::load_from_file();
+    let mut models = ModelList::load_or_recover()?;
     
     loop {
+        // 回到菜单时释放上一次修改持有的锁
+        models.file.unlock();
         println!("================================================================================");
         println!("🔧 模型配置菜单");
         println!("--------------------------------------------------------------------------------");
@@ -209,6 +181,12 @@ pub fn model_management() -> Option<Model> {
 
         let choice = Menu::form_handler(&input);
 
+        // 修改到保存期间持有锁，其他实例的修改不会夹在中间
+        if matches!(choice, Menu::ADD | Menu::EDIT | Menu::DELETE | Menu::SETDEFAULT)
+            && !models.file.begin_edit(&mut models.models) {
+            continue;
+        }
+
         match choice {
             Menu::ADD => {
                 println!("🔑 请输入API Key:");
@@ -420,10 +398,13 @@ pub fn model_management() -> Option<Model> {
         }
         
         // 保存到文件
-        if let Err(e) = models.save_to_file() {
+        if let Err(e) = models.save_to_file(false) {
             eprintln!("❌ 保存模型到文件失败: {}", e);
+            if is_conflict(&e) {
+                resolve_conflict(&mut models, |list| list.save_to_file(true), ModelList::reload);
+            }
         }
     }
     
     None
-}
\ No newline at end of file
+}
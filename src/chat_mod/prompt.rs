@@ -1,8 +1,9 @@
 use std::io::stdin;
-use std::fs;
-use std::path::Path;
 use serde::{Deserialize, Serialize};
 
+use crate::storage_mod::data_file::{is_conflict, resolve_conflict, DataFile};
+use crate::storage_mod::schema::{self, Schema};
+
 use crate::chat_mod::chat::{App, Message};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -11,89 +12,56 @@ pub struct Prompt {
     pub content: String,
 }
 
+const PROMPTS_FILE: &str = "prompts.json";
+/// prompts.json 的版本：0 为 `{"prompts": [...]}`，1 起数组包在 `{"version", "data"}` 中
+const SCHEMA: Schema = Schema { migrations: &[|data| schema::unwrap_field(data, "prompts")] };
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PromptList {
     pub prompts: Vec<Prompt>,
+    #[serde(skip)]
+    file: DataFile,
+}
+
+impl Default for PromptList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PromptList {
     pub fn new() -> Self {
         Self {
             prompts: Vec::new(),
+            file: DataFile::new(PROMPTS_FILE, SCHEMA),
         }
     }
 
-    pub fn load_from_file() -> Self {
-        let path = if cfg!(windows) {
-            // Windows系统使用AppData目录
-            dirs::data_local_dir().map(|mut p| {
-                p.push("SmallTool");
-                p.push("prompts.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取AppData目录，则使用当前目录
-                Path::new("prompts.json").to_path_buf()
-            })
-        } else {
-            // 非Windows系统保持原逻辑
-            dirs::data_dir().map(|mut p| {
-                p.push("small_tools");
-                p.push("prompts.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取数据目录，则使用当前目录
-                Path::new("prompts.json").to_path_buf()
-            })
-        };
-        
-        if path.exists() {
-            match fs::read_to_string(path) {
-                Ok(content) => {
-                    match serde_json::from_str(&content) {
-                        Ok(prompts) => prompts,
-                        Err(_) => PromptList::new(),
-                    }
-                }
-                Err(_) => PromptList::new(),
-            }
-        } else {
-            PromptList::new()
-        }
+    /// 文件不存在时返回空列表，无法解析时返回带行列号的错误而不是静默地当作空列表
+    pub fn load_from_file() -> Result<Self, std::io::Error> {
+        let mut list = PromptList::new();
+        list.reload()?;
+        Ok(list)
     }
 
-    pub fn save_to_file(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let path = if cfg!(windows) {
-            // Windows系统使用AppData目录
-            dirs::data_local_dir().map(|mut p| {
-                p.push("SmallTool");
-                p.push("prompts.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取AppData目录，则使用当前目录
-                Path::new("prompts.json").to_path_buf()
-            })
-        } else {
-            // 非Windows系统保持原逻辑
-            dirs::data_dir().map(|mut p| {
-                p.push("small_tools");
-                p.push("prompts.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取数据目录，则使用当前目录
-                Path::new("prompts.json").to_path_buf()
-            })
-        };
-        
-        // 确保目录存在
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        
-        let json = serde_json::to_string_pretty(self)?;
-        fs::write(path, json)?;
+    /// 通过同一个数据文件重新读取，修改期间持有的锁仍然有效
+    pub fn reload(&mut self) -> Result<(), std::io::Error> {
+        self.prompts = self.file.read_json()?.unwrap_or_default();
         Ok(())
     }
 
+    /// 交互菜单中加载，无法解析时询问是否备份损坏的文件后以空列表继续，放弃时返回 None
+    pub fn load_or_recover() -> Option<Self> {
+        let mut list = PromptList::new();
+        list.prompts = list.file.read_or_recover()?;
+        Some(list)
+    }
+
+    /// 文件在读取后被其他实例修改过时返回冲突错误而不覆盖，`force` 为 true 时直接覆盖
+    pub fn save_to_file(&mut self, force: bool) -> Result<(), std::io::Error> {
+        self.file.write_json(&self.prompts, force)
+    }
+
     pub fn add_prompt(&mut self, prompt: Prompt) {
         self.prompts.push(prompt);
     }
@@ -137,6 +105,8 @@ impl PromptList {
     }
 }
 
+// 菜单项沿用全大写的命名
+#[allow(clippy::upper_case_acronyms)]
 enum Menu {
     ADD,
     EDIT,
@@ -146,7 +116,7 @@ enum Menu {
 }
 
 impl Menu {
-    fn form_handler(str: &String) -> Self{
+    fn form_handler(str: &str) -> Self{
         match str.trim().to_lowercase().as_str() {
             "1"|"add" => Menu::ADD,
             "2"|"edit" => Menu::EDIT,
@@ -160,7 +130,9 @@ impl Menu {
 }
 
 pub fn prompt(app: &mut App) -> bool{
-    let mut prompts = PromptList::load_from_file();
+    let Some(mut prompts) = PromptList::load_or_recover() else {
+        return false;
+    };
     
     println!("================================================================================");
     println!("🔧 Prompt 配置菜单");
@@ -183,6 +155,11 @@ pub fn prompt(app: &mut App) -> bool{
 
     let choice = Menu::form_handler(&input);
 
+    // 修改到保存期间持有锁，其他实例的修改不会夹在中间
+    if matches!(choice, Menu::ADD | Menu::EDIT | Menu::DELETE) && !prompts.file.begin_edit(&mut prompts.prompts) {
+        return false;
+    }
+
     match choice {
         Menu::ADD => {
             println!("👤 请输入Role:");
@@ -319,10 +296,10 @@ pub fn prompt(app: &mut App) -> bool{
             }
             
             if let Some(selected_prompt) = prompts.get_prompt(index - 1) {
-                // 清空当前messages并添加选中的prompt作为第一条消息
-                app.request_body.messages.clear();
+                // 开始新会话并添加选中的prompt作为第一条消息，之前的对话保留在会话列表中
+                app.new_session();
                 app.request_body.messages.push(Message {
-                    role: String::from("user"),
+                    role: String::from("system"),
                     content: selected_prompt.content.clone(),
                 });
                 app.assistant_name = selected_prompt.role.clone();
@@ -335,9 +312,12 @@ pub fn prompt(app: &mut App) -> bool{
     }
     
     // 保存到文件
-    if let Err(e) = prompts.save_to_file() {
+    if let Err(e) = prompts.save_to_file(false) {
         eprintln!("❌ 保存Prompt到文件失败: {}", e);
+        if is_conflict(&e) {
+            resolve_conflict(&mut prompts, |list| list.save_to_file(true), PromptList::reload);
+        }
     }
     
     true
-}
\ No newline at end of file
+}
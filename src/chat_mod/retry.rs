@@ -0,0 +1,235 @@
+use std::hash::{BuildHasher, RandomState};
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Retry-After 超过这个时长时不再自动等待
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+/// 自动重试的策略，默认最多重试 3 次，首次等待约 1 秒，之后每次翻倍，最长 30 秒；
+/// 可通过环境变量 `CHAT_MAX_RETRIES`、`CHAT_RETRY_DELAY`、`CHAT_RETRY_MAX_DELAY`（秒）调整
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+fn env_seconds(name: &str) -> Option<Duration> {
+    std::env::var(name).ok()?.trim().parse::<f64>().ok()
+        .filter(|s| s.is_finite() && *s >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_retries: std::env::var("CHAT_MAX_RETRIES").ok()
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(default.max_retries),
+            base_delay: env_seconds("CHAT_RETRY_DELAY").unwrap_or(default.base_delay),
+            max_delay: env_seconds("CHAT_RETRY_MAX_DELAY").unwrap_or(default.max_delay),
+        }
+    }
+
+    /// 第 attempt 次重试（从 0 开始）前的等待时间，重试次数用尽时返回 None：
+    /// 服务端给出 Retry-After 时以其为准，超过 `MAX_RETRY_AFTER` 时同样返回 None 表示不再重试；
+    /// 否则按指数退避，并在后一半区间内随机取值，避免多个客户端同时重试
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= MAX_RETRY_AFTER).then_some(retry_after);
+        }
+        let backoff = self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let jitter = RandomState::new().hash_one(Instant::now()) as f64 / u64::MAX as f64;
+        Some(backoff / 2 + backoff.mul_f64(jitter / 2.0))
+    }
+}
+
+/// 解析 Retry-After 响应头，支持秒数和 HTTP 日期两种形式
+pub fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&chrono::Utc) - now).to_std().unwrap_or_default())
+}
+
+/// 倒计时期间临时接管 SIGINT，结束后恢复原来的处理方式，其他时候 Ctrl+C 照常终止程序。
+/// tokio 的信号监听一经注册便不会交还默认处理，因此这里直接使用 sigaction
+#[cfg(unix)]
+mod sigint {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    static PRESSED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn on_sigint(_: libc::c_int) {
+        PRESSED.store(true, Ordering::SeqCst);
+    }
+
+    /// 丢弃时恢复接管前的处理方式
+    pub struct Guard(Option<libc::sigaction>);
+
+    impl Guard {
+        pub fn install() -> Guard {
+            PRESSED.store(false, Ordering::SeqCst);
+            // SAFETY: 处理函数只写入原子变量，是异步信号安全的；结构体按 C 的约定零初始化
+            unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                action.sa_sigaction = on_sigint as extern "C" fn(libc::c_int) as libc::sighandler_t;
+                libc::sigemptyset(&mut action.sa_mask);
+                let mut previous: libc::sigaction = std::mem::zeroed();
+                if libc::sigaction(libc::SIGINT, &action, &mut previous) == 0 {
+                    Guard(Some(previous))
+                } else {
+                    Guard(None)
+                }
+            }
+        }
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            if let Some(previous) = &self.0 {
+                // SAFETY: previous 是 sigaction 返回的原处理方式
+                unsafe {
+                    libc::sigaction(libc::SIGINT, previous, std::ptr::null_mut());
+                }
+            }
+        }
+    }
+
+    pub async fn pressed() {
+        while !PRESSED.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+}
+
+// 非 Unix 平台上，tokio 的 Ctrl+C 监听被丢弃后会交还系统的默认处理
+#[cfg(not(unix))]
+async fn ctrl_c() {
+    if tokio::signal::ctrl_c().await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(unix)]
+async fn ctrl_c() {
+    sigint::pressed().await;
+}
+
+/// 显示重试倒计时，期间按 Ctrl+C 取消；等待结束返回 true，被取消返回 false
+pub async fn countdown(delay: Duration, attempt: u32, max_retries: u32) -> bool {
+    #[cfg(unix)]
+    let _guard = sigint::Guard::install();
+    let deadline = Instant::now() + delay;
+    let interactive = std::io::stderr().is_terminal();
+    let wait = async {
+        let mut shown = None;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            // 终端中每秒刷新剩余时间，输出被重定向时只提示一次
+            let seconds = remaining.as_secs_f64().ceil() as u64;
+            if shown != Some(seconds) && (interactive || shown.is_none()) {
+                eprint!(
+                    "{}⏳ {} 秒后进行第 {}/{} 次重试，按 Ctrl+C 取消   ",
+                    if interactive { "\r" } else { "" }, seconds, attempt, max_retries
+                );
+                let _ = std::io::stderr().flush();
+                shown = Some(seconds);
+            }
+            tokio::time::sleep(remaining.min(Duration::from_millis(100))).await;
+        }
+    };
+    let finished = tokio::select! {
+        _ = wait => true,
+        _ = ctrl_c() => false,
+    };
+    if finished {
+        eprintln!();
+    } else {
+        eprintln!("\n🚫 已取消重试");
+    }
+    finished
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn policy(max_retries: u32, base: u64, max: u64) -> RetryPolicy {
+        RetryPolicy { max_retries, base_delay: Duration::from_secs(base), max_delay: Duration::from_secs(max) }
+    }
+
+    // 多次取值，确认都落在 [backoff / 2, backoff] 区间内
+    fn assert_jitter_within(policy: &RetryPolicy, attempt: u32, backoff: Duration) {
+        for _ in 0..50 {
+            let delay = policy.delay(attempt, None).unwrap();
+            assert!(delay >= backoff / 2 && delay <= backoff, "第 {} 次: {:?} 不在 {:?} 的后一半区间内", attempt, delay, backoff);
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max_delay() {
+        let policy = policy(10, 1, 5);
+        assert_jitter_within(&policy, 0, Duration::from_secs(1));
+        assert_jitter_within(&policy, 1, Duration::from_secs(2));
+        assert_jitter_within(&policy, 2, Duration::from_secs(4));
+        assert_jitter_within(&policy, 3, Duration::from_secs(5));
+        assert_jitter_within(&policy, 9, Duration::from_secs(5));
+        // 指数很大时不会溢出
+        assert_jitter_within(&RetryPolicy { max_retries: u32::MAX, ..policy }, 200, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn no_delay_once_retries_are_exhausted() {
+        let policy = policy(2, 1, 30);
+        assert!(policy.delay(1, None).is_some());
+        assert_eq!(policy.delay(2, None), None);
+        assert_eq!(policy.delay(2, Some(Duration::from_secs(1))), None);
+        assert_eq!(RetryPolicy { max_retries: 0, ..policy }.delay(0, None), None);
+    }
+
+    #[test]
+    fn retry_after_is_used_as_is_up_to_cap() {
+        let policy = policy(3, 1, 2);
+        assert_eq!(policy.delay(0, Some(Duration::from_secs(10))), Some(Duration::from_secs(10)));
+        assert_eq!(policy.delay(0, Some(Duration::ZERO)), Some(Duration::ZERO));
+        assert_eq!(policy.delay(0, Some(MAX_RETRY_AFTER)), Some(MAX_RETRY_AFTER));
+        assert_eq!(policy.delay(0, Some(MAX_RETRY_AFTER + Duration::from_secs(1))), None);
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_and_dates() {
+        let now = chrono::Utc.with_ymd_and_hms(2015, 10, 21, 7, 27, 30).unwrap();
+        assert_eq!(parse_retry_after(" 17 ", now), Some(Duration::from_secs(17)));
+        assert_eq!(parse_retry_after("0", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 09:28:00 +0200", now), Some(Duration::from_secs(30)));
+        // 已经过去的时间按 0 处理
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("-5", now), None);
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
@@ -0,0 +1,304 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::chat_mod::chat::Message;
+use crate::storage_mod::data_file::{data_path, DataFile};
+use crate::storage_mod::schema::{self, Schema};
+
+const SESSIONS_DIR: &str = "sessions";
+/// 会话文件的版本，从 1 开始即带外层结构
+const SCHEMA: Schema = Schema { migrations: &[schema::wrap] };
+/// 标题最多保留的字符数
+const TITLE_LEN: usize = 30;
+
+/// 一次保存下来的对话，每个会话保存为 sessions 目录下的 `<ID>.json`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Session {
+    /// 创建时间，如 20261018-153000
+    pub id: String,
+    /// 为空时使用第一条提问作为标题
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub model: String,
+    /// 选择的 Prompt 角色名，未选择时为 None
+    #[serde(default)]
+    pub prompt: Option<String>,
+    pub created_time: i64,
+    pub updated_time: i64,
+    #[serde(default)]
+    pub messages: Vec<Message>,
+    #[serde(skip)]
+    file: DataFile,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn sessions_dir() -> PathBuf {
+    data_path(SESSIONS_DIR)
+}
+
+fn session_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::new_in(&sessions_dir())
+    }
+
+    fn new_in(dir: &Path) -> Self {
+        let now = chrono::Local::now();
+        let stamp = now.format("%Y%m%d-%H%M%S").to_string();
+        // 同一秒内新建多个会话时加上序号
+        let mut id = stamp.clone();
+        let mut seq = 1;
+        while session_path(dir, &id).exists() {
+            id = format!("{}-{}", stamp, seq);
+            seq += 1;
+        }
+        Self {
+            file: DataFile::at(session_path(dir, &id), SCHEMA),
+            id,
+            title: String::new(),
+            model: String::new(),
+            prompt: None,
+            created_time: now.timestamp(),
+            updated_time: now.timestamp(),
+            messages: Vec::new(),
+        }
+    }
+
+    fn load(dir: &Path, id: &str) -> io::Result<Session> {
+        let mut file = DataFile::at(session_path(dir, id), SCHEMA);
+        let mut session: Session = file.read_json()?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("会话 {} 不存在", id)))?;
+        session.file = file;
+        Ok(session)
+    }
+
+    /// 保存会话；文件已被其他实例修改时返回冲突错误
+    pub fn save(&mut self) -> io::Result<()> {
+        self.updated_time = chrono::Local::now().timestamp();
+        let session = self.clone();
+        self.file.write_json(&session, false)
+    }
+
+    /// 以新的 ID 保存当前内容，用于原会话已被其他实例修改的情况
+    pub fn fork(&mut self) -> io::Result<()> {
+        let dir = self.file.path().parent().map(Path::to_path_buf).unwrap_or_else(sessions_dir);
+        let fresh = Session::new_in(&dir);
+        self.id = fresh.id;
+        self.file = fresh.file;
+        self.created_time = fresh.created_time;
+        self.save()
+    }
+
+    /// 设置了标题时返回标题，否则为第一条提问
+    pub fn display_title(&self) -> String {
+        if !self.title.is_empty() {
+            return self.title.clone();
+        }
+        let first = self.messages.iter()
+            .find(|m| m.role == "user")
+            .map(|m| m.content.split_whitespace().collect::<Vec<_>>().join(" "))
+            .unwrap_or_else(|| String::from("（空会话）"));
+        match first.char_indices().nth(TITLE_LEN) {
+            Some((end, _)) => format!("{}…", &first[..end]),
+            None => first,
+        }
+    }
+
+    /// 不含 Prompt 等 system 消息的消息条数
+    pub fn message_count(&self) -> usize {
+        self.messages.iter().filter(|m| m.role != "system").count()
+    }
+
+    pub fn show(&self, index: usize, current: bool) {
+        let updated = chrono::DateTime::from_timestamp(self.updated_time, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        println!("{} {}. {}  {}", if current { "*" } else { " " }, index + 1, self.id, self.display_title());
+        println!("     {} · {} 条消息 · {}", self.model, self.message_count(), updated);
+    }
+}
+
+/// 列出所有会话，最近更新的在前；无法读取的会话文件给出提示后跳过
+pub fn list() -> io::Result<Vec<Session>> {
+    list_in(&sessions_dir())
+}
+
+fn list_in(dir: &Path) -> io::Result<Vec<Session>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut sessions = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let Some(id) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".json")) else {
+            continue;
+        };
+        match Session::load(dir, id) {
+            Ok(session) => sessions.push(session),
+            Err(e) => eprintln!("⚠️ 跳过无法读取的会话: {}", e),
+        }
+    }
+    sessions.sort_by(|a, b| b.updated_time.cmp(&a.updated_time).then_with(|| b.id.cmp(&a.id)));
+    Ok(sessions)
+}
+
+/// 按 ID、`list` 中的序号或唯一的 ID 前缀查找会话，前缀匹配多个会话时返回错误
+pub fn find(key: &str) -> io::Result<Option<Session>> {
+    find_in(&sessions_dir(), key)
+}
+
+fn find_in(dir: &Path, key: &str) -> io::Result<Option<Session>> {
+    let key = key.trim();
+    let mut sessions = list_in(dir)?;
+    if let Some(index) = sessions.iter().position(|s| s.id == key) {
+        return Ok(Some(sessions.swap_remove(index)));
+    }
+    if let Ok(n) = key.parse::<usize>() && n >= 1 && n <= sessions.len() {
+        return Ok(Some(sessions.swap_remove(n - 1)));
+    }
+    let matches: Vec<usize> = sessions.iter().enumerate()
+        .filter(|(_, s)| !key.is_empty() && s.id.starts_with(key))
+        .map(|(i, _)| i)
+        .collect();
+    match matches[..] {
+        [] => Ok(None),
+        [index] => Ok(Some(sessions.swap_remove(index))),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!(
+            "{} 匹配多个会话: {}",
+            key,
+            matches.iter().map(|&i| sessions[i].id.as_str()).collect::<Vec<_>>().join("、")
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage_mod::data_file::temp_dir;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message { role: role.to_string(), content: content.to_string() }
+    }
+
+    // 直接写入会话文件，保留给定的更新时间
+    fn store(dir: &Path, id: &str, updated_time: i64, question: &str) {
+        let mut session = Session::new_in(dir);
+        session.id = id.to_string();
+        session.updated_time = updated_time;
+        session.messages = vec![message("user", question), message("assistant", "好的")];
+        let mut file = DataFile::at(session_path(dir, id), SCHEMA);
+        file.write_json(&session, false).unwrap();
+    }
+
+    fn ids(sessions: &[Session]) -> Vec<&str> {
+        sessions.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn new_ids_are_timestamps_with_sequence() {
+        let dir = temp_dir("session-ids");
+        // 占用当前及之后两秒的 ID 和第一个序号，无论测试跨过哪一秒都应得到序号 2
+        let now = chrono::Local::now();
+        for offset in 0..3 {
+            let stamp = (now + chrono::Duration::seconds(offset)).format("%Y%m%d-%H%M%S").to_string();
+            fs::write(session_path(&dir, &stamp), "").unwrap();
+            fs::write(session_path(&dir, &format!("{}-1", stamp)), "").unwrap();
+        }
+        let session = Session::new_in(&dir);
+        let (stamp, seq) = session.id.rsplit_once('-').unwrap();
+        assert_eq!(seq, "2");
+        assert!(chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S").is_ok(), "{}", session.id);
+        assert_eq!(session.file.path(), session_path(&dir, &session.id));
+    }
+
+    #[test]
+    fn saves_and_forks_on_conflict() {
+        let dir = temp_dir("session-fork");
+        let mut session = Session::new_in(&dir);
+        session.messages.push(message("user", "第一问"));
+        session.save().unwrap();
+
+        // 另一个窗口继续了同一个会话
+        let mut other = Session::load(&dir, &session.id).unwrap();
+        other.messages.push(message("assistant", "另一个窗口的回答"));
+        other.save().unwrap();
+
+        session.messages.push(message("assistant", "本窗口的回答"));
+        let error = session.save().unwrap_err();
+        assert!(crate::storage_mod::data_file::is_conflict(&error));
+        let original = session.id.clone();
+        session.fork().unwrap();
+        assert_ne!(session.id, original);
+
+        let forked = Session::load(&dir, &session.id).unwrap();
+        assert_eq!(forked.messages.last().unwrap().content, "本窗口的回答");
+        let kept = Session::load(&dir, &original).unwrap();
+        assert_eq!(kept.messages.last().unwrap().content, "另一个窗口的回答");
+        // 另存后可以继续正常保存
+        session.save().unwrap();
+    }
+
+    #[test]
+    fn lists_recent_first_and_skips_unreadable() {
+        let dir = temp_dir("session-list");
+        store(&dir, "20261001-090000", 100, "旧的");
+        store(&dir, "20261002-090000", 300, "最新");
+        store(&dir, "20261003-090000", 200, "中间");
+        fs::write(dir.join("20261004-090000.json"), "{").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        assert_eq!(ids(&list_in(&dir).unwrap()), ["20261002-090000", "20261003-090000", "20261001-090000"]);
+        assert!(list_in(&dir.join("missing")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn finds_by_id_index_and_prefix() {
+        let dir = temp_dir("session-find");
+        store(&dir, "20261001-090000", 100, "十月一日");
+        store(&dir, "20261002-090000", 300, "十月二日上午");
+        store(&dir, "20261002-180000", 200, "十月二日晚上");
+        let find = |key: &str| find_in(&dir, key).unwrap().map(|s| s.id);
+
+        assert_eq!(find("20261001-090000").as_deref(), Some("20261001-090000"));
+        assert_eq!(find(" 2 ").as_deref(), Some("20261002-180000"));
+        assert_eq!(find("4"), None);
+        assert_eq!(find("20261001").as_deref(), Some("20261001-090000"));
+        assert_eq!(find("20261002-18").as_deref(), Some("20261002-180000"));
+        assert_eq!(find("2027"), None);
+        assert_eq!(find(""), None);
+        let error = find_in(&dir, "20261002").unwrap_err();
+        assert!(error.to_string().contains("20261002-090000、20261002-180000"), "{}", error);
+    }
+
+    #[test]
+    fn title_and_count_skip_prompt() {
+        let mut session = Session::new_in(&temp_dir("session-title"));
+        assert_eq!(session.display_title(), "（空会话）");
+        session.prompt = Some(String::from("翻译"));
+        session.messages = vec![
+            message("system", "你是翻译"),
+            message("user", "  把这句话\n翻译成英文  "),
+            message("assistant", "OK"),
+        ];
+        assert_eq!(session.display_title(), "把这句话 翻译成英文");
+        assert_eq!(session.message_count(), 2);
+
+        session.messages[1].content = "字".repeat(40);
+        assert_eq!(session.display_title(), format!("{}…", "字".repeat(TITLE_LEN)));
+        session.title = String::from("自定义标题");
+        assert_eq!(session.display_title(), "自定义标题");
+    }
+}
@@ -0,0 +1,204 @@
+/// 一个 Server-Sent Events 事件
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Event {
+    /// `event:` 字段，未指定时为 None（即默认的 message 类型）
+    pub event: Option<String>,
+    /// 所有 `data:` 行以换行连接后的内容
+    pub data: String,
+    pub id: Option<String>,
+}
+
+impl Event {
+    /// OpenAI 兼容接口以 `data: [DONE]` 表示流结束
+    pub fn is_done(&self) -> bool {
+        self.data.trim() == "[DONE]"
+    }
+}
+
+/// 增量的 SSE 解码器：网络分块可以在任意字节处断开，
+/// 未完整的行留在缓冲区中，等后续数据到达后再解析
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    /// 上一块以 `\r` 结尾时，下一块开头的 `\n` 属于同一个换行
+    skip_lf: bool,
+    /// 流开头的 BOM 已经处理过
+    started: bool,
+    event: Option<String>,
+    data: Option<String>,
+    id: Option<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一块数据，返回其中已经完整的事件
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Event> {
+        let mut events = Vec::new();
+        for &byte in bytes {
+            if self.skip_lf {
+                self.skip_lf = false;
+                if byte == b'\n' {
+                    continue;
+                }
+            }
+            match byte {
+                b'\r' => {
+                    self.skip_lf = true;
+                    self.end_line(&mut events);
+                },
+                b'\n' => self.end_line(&mut events),
+                _ => self.buffer.push(byte),
+            }
+        }
+        events
+    }
+
+    /// 流结束时调用：按规范未以空行结束的事件应丢弃，但有些服务端最后一个事件后
+    /// 不发送空行，这里仍把剩余的内容当作一个完整事件返回
+    pub fn finish(&mut self) -> Option<Event> {
+        let mut events = Vec::new();
+        if !self.buffer.is_empty() {
+            self.end_line(&mut events);
+        }
+        self.end_line(&mut events);
+        events.pop()
+    }
+
+    fn end_line(&mut self, events: &mut Vec<Event>) {
+        let mut line = String::from_utf8_lossy(&self.buffer).into_owned();
+        self.buffer.clear();
+        if !self.started {
+            self.started = true;
+            if let Some(rest) = line.strip_prefix('\u{feff}') {
+                line = rest.to_string();
+            }
+        }
+        if line.is_empty() {
+            self.dispatch(events);
+            return;
+        }
+        if line.starts_with(':') {
+            // 注释，常用作心跳
+            return;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_str(), ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => match self.data.as_mut() {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                },
+                None => self.data = Some(value.to_string()),
+            },
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            // retry 和未知字段按规范忽略
+            _ => {},
+        }
+    }
+
+    // 空行表示一个事件结束；没有 data 的事件不分发
+    fn dispatch(&mut self, events: &mut Vec<Event>) {
+        let event = self.event.take();
+        if let Some(data) = self.data.take() {
+            events.push(Event { event, data, id: self.id.clone() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_chunks(chunks: &[&[u8]]) -> Vec<Event> {
+        let mut decoder = SseDecoder::new();
+        let mut events: Vec<Event> = chunks.iter().flat_map(|c| decoder.feed(c)).collect();
+        events.extend(decoder.finish());
+        events
+    }
+
+    fn data(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|e| e.data.as_str()).collect()
+    }
+
+    const STREAM: &str = "\u{feff}: keep-alive\r\n\
+        data: {\"choices\":[{\"delta\":{\"content\":\"你好\"}}]}\r\n\r\n\
+        event: ping\n\
+        data: 1\n\n\
+        id: 7\n\
+        data: 第一行\n\
+        data:第二行\n\
+        data\n\n\
+        data: [DONE]\r\r";
+
+    #[test]
+    fn decodes_whole_stream() {
+        let events = decode_chunks(&[STREAM.as_bytes()]);
+        assert_eq!(data(&events), [
+            "{\"choices\":[{\"delta\":{\"content\":\"你好\"}}]}",
+            "1",
+            "第一行\n第二行\n",
+            "[DONE]",
+        ]);
+        assert_eq!(events[0].event, None);
+        assert_eq!(events[1].event.as_deref(), Some("ping"));
+        assert_eq!(events[2].id.as_deref(), Some("7"));
+        assert!(events[3].is_done());
+    }
+
+    #[test]
+    fn any_split_point_gives_same_events() {
+        let bytes = STREAM.as_bytes();
+        let expected = decode_chunks(&[bytes]);
+        for i in 0..=bytes.len() {
+            for j in i..=bytes.len() {
+                let events = decode_chunks(&[&bytes[..i], &bytes[i..j], &bytes[j..]]);
+                assert_eq!(events, expected, "split at {} and {}", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn byte_by_byte_input() {
+        let chunks: Vec<&[u8]> = STREAM.as_bytes().chunks(1).collect();
+        assert_eq!(decode_chunks(&chunks), decode_chunks(&[STREAM.as_bytes()]));
+    }
+
+    #[test]
+    fn crlf_split_between_chunks_is_one_line_break() {
+        let events = decode_chunks(&[b"data: a\r", b"\ndata: b\r", b"\n\r", b"\n"]);
+        assert_eq!(data(&events), ["a\nb"]);
+    }
+
+    #[test]
+    fn comments_and_events_without_data_are_skipped() {
+        let events = decode_chunks(&[b": comment\n\nevent: empty\n\nretry: 100\nfoo: bar\n\ndata: x\n\n"]);
+        assert_eq!(data(&events), ["x"]);
+        assert_eq!(events[0].event, None, "event type must not leak from the dropped event");
+    }
+
+    #[test]
+    fn last_event_without_trailing_blank_line() {
+        let events = decode_chunks(&[b"data: a\n\ndata: [DO", b"NE]"]);
+        assert_eq!(data(&events), ["a", "[DONE]"]);
+    }
+
+    #[test]
+    fn finish_without_pending_data() {
+        let mut decoder = SseDecoder::new();
+        assert_eq!(decoder.feed(b"data: a\n\n").len(), 1);
+        assert_eq!(decoder.finish(), None);
+    }
+
+    #[test]
+    fn id_persists_across_events() {
+        let events = decode_chunks(&[b"id: 1\ndata: a\n\ndata: b\n\n"]);
+        assert_eq!(events[1].id.as_deref(), Some("1"));
+    }
+}
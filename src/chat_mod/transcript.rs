@@ -0,0 +1,247 @@
+use serde::Serialize;
+
+use crate::chat_mod::chat::Message;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+impl TranscriptFormat {
+    pub fn parse(input: &str) -> Option<TranscriptFormat> {
+        match input.trim().to_lowercase().as_str() {
+            "md" | "markdown" => Some(TranscriptFormat::Markdown),
+            "json" => Some(TranscriptFormat::Json),
+            "html" | "htm" => Some(TranscriptFormat::Html),
+            _ => None,
+        }
+    }
+
+    /// 根据文件扩展名推断格式
+    pub fn from_path(path: &str) -> Option<TranscriptFormat> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?;
+        TranscriptFormat::parse(ext)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TranscriptFormat::Markdown => "md",
+            TranscriptFormat::Json => "json",
+            TranscriptFormat::Html => "html",
+        }
+    }
+
+    pub fn render(&self, transcript: &Transcript) -> Result<String, String> {
+        match self {
+            TranscriptFormat::Markdown => Ok(to_markdown(transcript)),
+            TranscriptFormat::Json => to_json(transcript),
+            TranscriptFormat::Html => Ok(to_html(transcript)),
+        }
+    }
+}
+
+/// 要导出的一段对话
+#[derive(Debug)]
+pub struct Transcript<'a> {
+    pub title: String,
+    pub model: &'a str,
+    /// 选择的 Prompt 角色名，此时 system 消息是 Prompt 内容
+    pub prompt: Option<&'a str>,
+    pub messages: &'a [Message],
+}
+
+impl Transcript<'_> {
+    // 按消息的 role 决定显示的说话人，而不是按消息的先后顺序
+    fn speaker(&self, message: &Message) -> String {
+        match message.role.as_str() {
+            "user" => String::from("用户"),
+            "assistant" => self.prompt.unwrap_or("Assistant").to_string(),
+            "system" if self.prompt.is_some() => String::from("Prompt"),
+            "system" => String::from("系统"),
+            other => other.to_string(),
+        }
+    }
+
+    // 用于 HTML 样式的类名
+    fn kind(&self, message: &Message) -> &'static str {
+        match message.role.as_str() {
+            "user" => "user",
+            "assistant" => "assistant",
+            "system" if self.prompt.is_some() => "prompt",
+            _ => "system",
+        }
+    }
+}
+
+fn exported_at() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn to_markdown(t: &Transcript) -> String {
+    let mut out = format!("# {}\n\n- 模型: {}\n- 导出时间: {}\n", t.title, t.model, exported_at());
+    for message in t.messages {
+        out.push_str(&format!("\n## {}\n\n{}\n", t.speaker(message), message.content.trim_end()));
+    }
+    out
+}
+
+// JSON 导出保留原始的 role 和 content
+#[derive(Serialize)]
+struct JsonTranscript<'a> {
+    title: &'a str,
+    model: &'a str,
+    prompt: Option<&'a str>,
+    exported_at: String,
+    messages: &'a [Message],
+}
+
+fn to_json(t: &Transcript) -> Result<String, String> {
+    let value = JsonTranscript {
+        title: &t.title,
+        model: t.model,
+        prompt: t.prompt,
+        exported_at: exported_at(),
+        messages: t.messages,
+    };
+    serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// 样式内嵌在页面中，不依赖任何外部资源
+const HTML_STYLE: &str = "\
+body { font-family: -apple-system, 'Segoe UI', 'PingFang SC', 'Microsoft YaHei', sans-serif; \
+max-width: 860px; margin: 2em auto; padding: 0 1em; color: #222; background: #fafafa; }
+h1 { font-size: 1.5em; margin-bottom: 0.2em; }
+.meta { color: #777; font-size: 0.9em; margin-bottom: 2em; }
+.message { margin: 1em 0; padding: 0.8em 1em; border-radius: 8px; border: 1px solid #e2e2e2; background: #fff; }
+.message.user { background: #eef5ff; border-color: #cfe0fb; }
+.message.prompt, .message.system { background: #fff8e6; border-color: #f1deb0; }
+.speaker { font-weight: bold; font-size: 0.9em; margin-bottom: 0.4em; color: #555; }
+.content { white-space: pre-wrap; word-wrap: break-word; line-height: 1.6; }";
+
+fn to_html(t: &Transcript) -> String {
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n", escape_html(&t.title), HTML_STYLE));
+    out.push_str(&format!("<h1>{}</h1>\n", escape_html(&t.title)));
+    out.push_str(&format!(
+        "<div class=\"meta\">模型: {} · 导出时间: {}</div>\n", escape_html(t.model), exported_at()
+    ));
+    for message in t.messages {
+        out.push_str(&format!(
+            "<div class=\"message {}\">\n<div class=\"speaker\">{}</div>\n<div class=\"content\">{}</div>\n</div>\n",
+            t.kind(message),
+            escape_html(&t.speaker(message)),
+            escape_html(message.content.trim_end()),
+        ));
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message { role: role.to_string(), content: content.to_string() }
+    }
+
+    fn transcript<'a>(prompt: Option<&'a str>, messages: &'a [Message]) -> Transcript<'a> {
+        Transcript { title: String::from("测试 <对话>"), model: "gpt-test", prompt, messages }
+    }
+
+    fn headings(markdown: &str) -> Vec<&str> {
+        markdown.lines().filter_map(|l| l.strip_prefix("## ")).collect()
+    }
+
+    fn html_kinds(html: &str) -> Vec<&str> {
+        html.split("<div class=\"message ").skip(1).filter_map(|s| s.split('"').next()).collect()
+    }
+
+    #[test]
+    fn parses_format_names_and_extensions() {
+        assert_eq!(TranscriptFormat::parse(" MD "), Some(TranscriptFormat::Markdown));
+        assert_eq!(TranscriptFormat::parse("htm"), Some(TranscriptFormat::Html));
+        assert_eq!(TranscriptFormat::parse("txt"), None);
+        assert_eq!(TranscriptFormat::from_path("out/chat.json"), Some(TranscriptFormat::Json));
+        assert_eq!(TranscriptFormat::from_path("chat"), None);
+    }
+
+    #[test]
+    fn session_with_prompt() {
+        let messages = [message("system", "你是翻译"), message("user", "你好"), message("assistant", "Hello")];
+        let t = transcript(Some("翻译"), &messages);
+
+        let md = TranscriptFormat::Markdown.render(&t).unwrap();
+        assert!(md.starts_with("# 测试 <对话>\n\n- 模型: gpt-test\n"));
+        assert_eq!(headings(&md), ["Prompt", "用户", "翻译"]);
+        assert!(md.contains("## Prompt\n\n你是翻译\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&TranscriptFormat::Json.render(&t).unwrap()).unwrap();
+        assert_eq!(json["prompt"], "翻译");
+        assert_eq!(json["messages"][0]["role"], "system");
+        assert_eq!(json["messages"][2]["content"], "Hello");
+
+        let html = TranscriptFormat::Html.render(&t).unwrap();
+        assert_eq!(html_kinds(&html), ["prompt", "user", "assistant"]);
+        assert!(html.contains("<title>测试 &lt;对话&gt;</title>"));
+        assert!(html.contains("<div class=\"speaker\">翻译</div>"));
+    }
+
+    #[test]
+    fn session_without_prompt() {
+        let messages = [message("user", "<b>1 & 2</b>"), message("assistant", "3")];
+        let t = transcript(None, &messages);
+
+        let md = TranscriptFormat::Markdown.render(&t).unwrap();
+        assert_eq!(headings(&md), ["用户", "Assistant"]);
+
+        let json: serde_json::Value = serde_json::from_str(&TranscriptFormat::Json.render(&t).unwrap()).unwrap();
+        assert!(json["prompt"].is_null());
+        assert_eq!(json["messages"].as_array().unwrap().len(), 2);
+
+        let html = TranscriptFormat::Html.render(&t).unwrap();
+        assert_eq!(html_kinds(&html), ["user", "assistant"]);
+        assert!(html.contains("&lt;b&gt;1 &amp; 2&lt;/b&gt;"));
+    }
+
+    #[test]
+    fn first_message_is_not_taken_as_prompt() {
+        // 撤销后第一条消息不一定是 Prompt 内容
+        let messages = [message("user", "再来一次"), message("assistant", "好的")];
+        let t = transcript(Some("翻译"), &messages);
+        assert_eq!(headings(&TranscriptFormat::Markdown.render(&t).unwrap()), ["用户", "翻译"]);
+        assert_eq!(html_kinds(&TranscriptFormat::Html.render(&t).unwrap()), ["user", "assistant"]);
+        let json: serde_json::Value = serde_json::from_str(&TranscriptFormat::Json.render(&t).unwrap()).unwrap();
+        assert_eq!(json["messages"][0]["role"], "user");
+
+        // 全部撤销后只剩 Prompt
+        let messages = [message("system", "你是翻译")];
+        let t = transcript(Some("翻译"), &messages);
+        assert_eq!(headings(&TranscriptFormat::Markdown.render(&t).unwrap()), ["Prompt"]);
+        assert_eq!(html_kinds(&TranscriptFormat::Html.render(&t).unwrap()), ["prompt"]);
+
+        // 没有选择 Prompt 时 system 消息按系统消息显示
+        let messages = [message("system", "规则"), message("user", "你好")];
+        let t = transcript(None, &messages);
+        assert_eq!(headings(&TranscriptFormat::Markdown.render(&t).unwrap()), ["系统", "用户"]);
+        assert_eq!(html_kinds(&TranscriptFormat::Html.render(&t).unwrap()), ["system", "user"]);
+    }
+}
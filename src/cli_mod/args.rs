@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+/// 命令执行成功
+pub const EXIT_OK: i32 = 0;
+/// 执行过程中出错（未找到条目、读写失败、请求失败等）
+pub const EXIT_FAILURE: i32 = 1;
+/// 参数错误
+pub const EXIT_USAGE: i32 = 2;
+
+/// 输出参数错误和命令用法，返回 `EXIT_USAGE`
+pub fn usage_error(msg: &str, usage: &str) -> i32 {
+    eprintln!("❌ {}", msg);
+    eprintln!("{}", usage);
+    EXIT_USAGE
+}
+
+/// 命令行参数解析结果：`--key value` / `--key=value` 形式的选项、无值的开关以及位置参数
+#[derive(Debug, Default)]
+pub struct Args {
+    positionals: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    /// 解析参数列表，`options` 为需要取值的选项名，`flags` 为开关名（均不含 `--` 前缀）
+    pub fn parse(args: &[String], options: &[&str], flags: &[&str]) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg == "--" {
+                parsed.positionals.extend(iter.by_ref().cloned());
+                break;
+            }
+            let Some(name) = arg.strip_prefix("--") else {
+                parsed.positionals.push(arg.clone());
+                continue;
+            };
+            let (name, inline_value) = match name.split_once('=') {
+                Some((n, v)) => (n, Some(v.to_string())),
+                None => (name, None),
+            };
+            if flags.contains(&name) {
+                if inline_value.is_some() {
+                    return Err(format!("开关 --{} 不接受参数值", name));
+                }
+                parsed.flags.push(name.to_string());
+            } else if options.contains(&name) {
+                let value = match inline_value {
+                    Some(v) => v,
+                    None => iter.next()
+                        .cloned()
+                        .ok_or_else(|| format!("选项 --{} 缺少参数值", name))?,
+                };
+                parsed.options.insert(name.to_string(), value);
+            } else {
+                return Err(format!("未知选项: --{}", name));
+            }
+        }
+        Ok(parsed)
+    }
+
+    pub fn opt(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|s| s.as_str())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    pub fn positional(&self, index: usize) -> Option<&str> {
+        self.positionals.get(index).map(|s| s.as_str())
+    }
+
+    pub fn positionals(&self) -> &[String] {
+        &self.positionals
+    }
+}
@@ -1,2 +1,4 @@
 pub mod todo_mod;
-pub mod chat_mod;
\ No newline at end of file
+pub mod chat_mod;
+pub mod cli_mod;
+pub mod storage_mod;
@@ -1,5 +1,7 @@
-use small_tools::chat_mod::chat;
-use small_tools::todo_mod::todo_list;
+use small_tools::chat_mod::{chat, chat_cmd};
+use small_tools::cli_mod::args::usage_error;
+use small_tools::storage_mod::backup_cmd;
+use small_tools::todo_mod::{todo_cmd, todo_list};
 use std::io::stdin;
 
 
@@ -16,7 +18,36 @@ fn show_menu() {
     println!("请选择功能（输入数字或命令）：");
 }
 
+const USAGE: &str = "\
+用法: small_tools [命令]
+
+不带参数启动时进入交互式菜单。
+
+命令:
+  todo <子命令>    待办事项管理（add/list/edit/rm），详见 small_tools todo help
+  chat <子命令>    问答模式（ask），详见 small_tools chat help
+  backup <子命令>  数据备份（list/restore），详见 small_tools backup help";
+
+// 带参数时以非交互方式执行子命令，并以其返回值作为退出码
+fn run_command(args: &[String]) -> i32 {
+    match args[0].as_str() {
+        "todo" => todo_cmd::todo_cmd(&args[1..]),
+        "chat" => chat_cmd::chat_cmd(&args[1..]),
+        "backup" => backup_cmd::backup_cmd(&args[1..]),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            0
+        },
+        other => usage_error(&format!("未知命令: {}", other), USAGE),
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(run_command(&args));
+    }
+
     let mut input = String::new();
     
     show_menu();
@@ -47,6 +78,8 @@ fn main() {
     }
 }
 
+// 菜单项沿用全大写的命名
+#[allow(clippy::upper_case_acronyms)]
 enum Menu {
     TODO,
     CHAT,
@@ -54,7 +87,7 @@ enum Menu {
 }
 
 impl Menu {
-    fn form_handler(str: &String) -> Menu{
+    fn form_handler(str: &str) -> Menu{
         match str.trim().to_lowercase().as_str() {
             "1"|"todo_list" => {
                 Menu::TODO
@@ -0,0 +1,296 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use serde_json::Value;
+
+use crate::storage_mod::data_file::{data_path, write_atomic};
+use crate::storage_mod::schema::Schema;
+
+/// 每个数据文件保留的快照数量
+pub const KEEP: usize = 10;
+
+const TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+/// 按 TIME_FORMAT 格式化后的长度，如 20261018-153000
+const STAMP_LEN: usize = 15;
+
+/// 参与备份的数据文件
+#[derive(Debug, Clone, Copy)]
+pub struct Store {
+    pub file: &'static str,
+    /// 比较快照时用来识别同一条目的字段
+    key: &'static str,
+    /// 差异摘要中显示的字段
+    label: &'static str,
+}
+
+pub const STORES: &[Store] = &[
+    Store { file: "data.json", key: "id", label: "title" },
+    Store { file: "models.json", key: "model_name", label: "model_name" },
+    Store { file: "prompts.json", key: "content", label: "content" },
+];
+
+impl Store {
+    pub fn find(file: &str) -> Option<Store> {
+        STORES.iter().copied().find(|s| s.file == file || s.stem() == file)
+    }
+
+    fn stem(&self) -> &'static str {
+        self.file.trim_end_matches(".json")
+    }
+
+    pub fn path(&self) -> PathBuf {
+        data_path(self.file)
+    }
+}
+
+/// 一份快照，ID 即文件名去掉扩展名，如 `data-20261018-153000`
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub id: String,
+    pub store: Store,
+    pub path: PathBuf,
+    pub time: NaiveDateTime,
+    /// 同一秒内多次保存时的序号
+    seq: u32,
+}
+
+impl Snapshot {
+    // 从 `<名称>-<时间>[-<序号>].json` 形式的文件名解析
+    fn parse(path: &Path) -> Option<Snapshot> {
+        let id = path.file_name()?.to_str()?.strip_suffix(".json")?;
+        let (store, rest) = STORES.iter()
+            .find_map(|s| id.strip_prefix(s.stem())?.strip_prefix('-').map(|rest| (*s, rest)))?;
+        let time = NaiveDateTime::parse_from_str(rest.get(..STAMP_LEN)?, TIME_FORMAT).ok()?;
+        let seq = match rest.get(STAMP_LEN..)? {
+            "" => 0,
+            seq => seq.strip_prefix('-')?.parse().ok()?,
+        };
+        Some(Snapshot { id: id.to_string(), store, path: path.to_path_buf(), time, seq })
+    }
+}
+
+// 快照与数据文件放在同一目录下的 backups 子目录中
+fn backups_dir(data_file: &Path) -> PathBuf {
+    data_file.parent().unwrap_or(Path::new(".")).join("backups")
+}
+
+/// 覆盖数据文件前保存其当前内容，并只保留最近 `KEEP` 份；
+/// 文件不存在、不参与备份或内容没有变化时不生成快照
+pub fn snapshot(path: &Path, new_content: &[u8]) -> io::Result<Option<PathBuf>> {
+    let Some(store) = path.file_name().and_then(|n| n.to_str()).and_then(Store::find) else {
+        return Ok(None);
+    };
+    let old = match fs::read(path) {
+        Ok(old) => old,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if old == new_content {
+        return Ok(None);
+    }
+    let dir = backups_dir(path);
+    let now = chrono::Local::now().naive_local();
+    let stamp = now.format(TIME_FORMAT).to_string();
+    // 同一秒内已有快照时接在最大的序号之后，保证按 (时间, 序号) 排序后新快照总在最后
+    let seq = list_in(&dir, Some(store))?.iter()
+        .filter(|s| s.time.format(TIME_FORMAT).to_string() == stamp)
+        .map(|s| s.seq + 1)
+        .max();
+    let target = match seq {
+        Some(seq) => dir.join(format!("{}-{}-{}.json", store.stem(), stamp, seq)),
+        None => dir.join(format!("{}-{}.json", store.stem(), stamp)),
+    };
+    write_atomic(&target, &old)?;
+    let snapshots = list_in(&dir, Some(store))?;
+    for old in snapshots.iter().take(snapshots.len().saturating_sub(KEEP)) {
+        fs::remove_file(&old.path)?;
+    }
+    Ok(Some(target))
+}
+
+/// 列出快照，按时间从旧到新排列，`store` 为 None 时列出全部
+pub fn list(store: Option<Store>) -> io::Result<Vec<Snapshot>> {
+    list_in(&backups_dir(&data_path(STORES[0].file)), store)
+}
+
+fn list_in(dir: &Path, store: Option<Store>) -> io::Result<Vec<Snapshot>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        if let Some(snapshot) = Snapshot::parse(&entry?.path())
+            && store.is_none_or(|s| s.file == snapshot.store.file) {
+            snapshots.push(snapshot);
+        }
+    }
+    snapshots.sort_by(|a, b| (a.store.file, a.time, a.seq).cmp(&(b.store.file, b.time, b.seq)));
+    Ok(snapshots)
+}
+
+pub fn find(id: &str) -> io::Result<Option<Snapshot>> {
+    Ok(list(None)?.into_iter().find(|s| s.id == id.trim_end_matches(".json")))
+}
+
+/// 取出文件中的条目列表；旧版本的文件可能没有外层结构，或是 `{"models": [...]}` 这样的对象
+pub fn entries(content: &str) -> Result<Vec<Value>, String> {
+    let value: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let (_, data) = Schema::unwrap(value)?;
+    match data {
+        Value::Array(items) => Ok(items),
+        Value::Object(map) => map.into_iter()
+            .find_map(|(_, v)| match v {
+                Value::Array(items) => Some(items),
+                _ => None,
+            })
+            .ok_or_else(|| String::from("没有找到条目列表")),
+        _ => Err(String::from("没有找到条目列表")),
+    }
+}
+
+/// 用快照替换当前内容时条目的变化
+#[derive(Debug, Default)]
+pub struct Diff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+    pub unchanged: usize,
+}
+
+/// 比较当前内容与快照，结果表示恢复后相对当前会发生的变化
+pub fn diff(store: Store, current: &[Value], snapshot: &[Value]) -> Diff {
+    let key = |v: &Value| v.get(store.key).cloned().unwrap_or_default();
+    let label = |v: &Value| {
+        let text = v.get(store.label).and_then(Value::as_str).unwrap_or_default();
+        match text.char_indices().nth(30) {
+            Some((end, _)) => format!("{}…", &text[..end]),
+            None => text.to_string(),
+        }
+    };
+    let mut diff = Diff::default();
+    for item in snapshot {
+        match current.iter().find(|c| key(c) == key(item)) {
+            None => diff.added.push(label(item)),
+            Some(c) if c != item => diff.changed.push(label(item)),
+            Some(_) => diff.unchanged += 1,
+        }
+    }
+    for item in current {
+        if !snapshot.iter().any(|s| key(s) == key(item)) {
+            diff.removed.push(label(item));
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage_mod::data_file::temp_dir;
+    use serde_json::json;
+
+    fn ids(snapshots: &[Snapshot]) -> Vec<&str> {
+        snapshots.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_snapshot_names() {
+        let parse = |name: &str| Snapshot::parse(Path::new(name));
+        let snapshot = parse("backups/models-20261018-153000-2.json").unwrap();
+        assert_eq!(snapshot.id, "models-20261018-153000-2");
+        assert_eq!(snapshot.store.file, "models.json");
+        assert_eq!(snapshot.seq, 2);
+        assert_eq!(parse("data-20261018-153000.json").unwrap().seq, 0);
+        assert!(parse("data-20261018-153000").is_none());
+        assert!(parse("notes-20261018-153000.json").is_none());
+        assert!(parse("data-20261018.json").is_none());
+        assert!(parse("data-20261018-153000-x.json").is_none());
+        assert!(parse("data-20261018-153000x.json").is_none());
+    }
+
+    #[test]
+    fn sequence_sorts_after_base_timestamp() {
+        let dir = temp_dir("backup-order");
+        for name in [
+            "data-20261018-153000-10.json",
+            "data-20261018-153000-2.json",
+            "data-20261018-153001.json",
+            "data-20261018-153000.json",
+            "data-20261018-152959.json",
+            "prompts-20261018-153000.json",
+            "readme.txt",
+        ] {
+            fs::write(dir.join(name), "[]").unwrap();
+        }
+        assert_eq!(ids(&list_in(&dir, Store::find("data")).unwrap()), [
+            "data-20261018-152959",
+            "data-20261018-153000",
+            "data-20261018-153000-2",
+            "data-20261018-153000-10",
+            "data-20261018-153001",
+        ]);
+        assert_eq!(list_in(&dir, None).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn keeps_latest_snapshots_per_store() {
+        let dir = temp_dir("backup-prune");
+        let data = dir.join("data.json");
+        let models = dir.join("models.json");
+        fs::write(&models, "[\"m\"]").unwrap();
+        snapshot(&models, b"[]").unwrap().unwrap();
+
+        assert!(snapshot(&data, b"[0]").unwrap().is_none(), "文件不存在时不生成快照");
+        for i in 0..KEEP + 3 {
+            fs::write(&data, format!("[{}]", i)).unwrap();
+            snapshot(&data, format!("[{}]", i + 1).as_bytes()).unwrap().unwrap();
+        }
+        assert!(snapshot(&data, format!("[{}]", KEEP + 2).as_bytes()).unwrap().is_none(), "内容未变时不生成快照");
+        assert!(snapshot(&dir.join("other.json"), b"[]").unwrap().is_none());
+
+        let backups = dir.join("backups");
+        let kept = list_in(&backups, Store::find("data")).unwrap();
+        assert_eq!(kept.len(), KEEP);
+        let contents: Vec<String> = kept.iter().map(|s| fs::read_to_string(&s.path).unwrap()).collect();
+        let expected: Vec<String> = (3..KEEP + 3).map(|i| format!("[{}]", i)).collect();
+        assert_eq!(contents, expected);
+        assert_eq!(list_in(&backups, Store::find("models")).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn entries_accept_old_and_new_layouts() {
+        assert_eq!(entries("{\"version\": 1, \"data\": [1]}"), Ok(vec![json!(1)]));
+        assert_eq!(entries("[1, 2]"), Ok(vec![json!(1), json!(2)]));
+        assert_eq!(entries("{\"models\": [3]}"), Ok(vec![json!(3)]));
+        assert!(entries("{\"version\": 1, \"data\": 5}").is_err());
+        assert!(entries("{").is_err());
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed() {
+        let store = Store::find("data.json").unwrap();
+        let current = [
+            json!({ "id": "a", "title": "保留" }),
+            json!({ "id": "b", "title": "修改前" }),
+            json!({ "id": "c", "title": "恢复后消失" }),
+        ];
+        let snapshot = [
+            json!({ "id": "a", "title": "保留" }),
+            json!({ "id": "b", "title": "修改后" }),
+            json!({ "id": "d", "title": "恢复后出现" }),
+        ];
+        let changes = diff(store, &current, &snapshot);
+        assert_eq!(changes.added, ["恢复后出现"]);
+        assert_eq!(changes.removed, ["恢复后消失"]);
+        assert_eq!(changes.changed, ["修改后"]);
+        assert_eq!(changes.unchanged, 1);
+
+        let long = "长".repeat(40);
+        let changes = diff(store, &[], &[json!({ "id": "e", "title": long })]);
+        assert_eq!(changes.added, [format!("{}…", "长".repeat(30))]);
+    }
+}
@@ -0,0 +1,164 @@
+use std::io::stdin;
+
+use crate::cli_mod::args::{usage_error, Args, EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+use crate::storage_mod::backup::{self, Store, KEEP, STORES};
+use crate::storage_mod::data_file::DataFile;
+use crate::storage_mod::schema::Schema;
+
+const USAGE: &str = "\
+用法: small_tools backup <子命令> [选项]
+
+每次保存 data.json、models.json、prompts.json 前都会把原内容保存为快照，每个文件保留最近 10 份。
+
+子命令:
+  list [--file <data.json|models.json|prompts.json>]
+      列出快照，ID 形如 data-20261018-153000
+  restore <ID> [--yes]
+      显示恢复后条目的变化并确认后用快照替换当前文件，--yes 跳过确认；
+      当前内容会先保存为新的快照，可以再次恢复";
+
+/// 备份命令入口，返回进程退出码
+pub fn backup_cmd(args: &[String]) -> i32 {
+    let Some((sub, rest)) = args.split_first() else {
+        eprintln!("{}", USAGE);
+        return EXIT_USAGE;
+    };
+    match sub.as_str() {
+        "list" | "ls" => list(rest),
+        "restore" => restore(rest),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            EXIT_OK
+        },
+        _ => usage_error(&format!("未知的子命令: {}", sub), USAGE),
+    }
+}
+
+fn list(args: &[String]) -> i32 {
+    let args = match Args::parse(args, &["file"], &[]) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e, USAGE),
+    };
+    let store = match args.opt("file").map(Store::find) {
+        Some(Some(store)) => Some(store),
+        Some(None) => return usage_error(&format!(
+            "未知的数据文件，可选值: {}",
+            STORES.iter().map(|s| s.file).collect::<Vec<_>>().join("/")
+        ), USAGE),
+        None => None,
+    };
+    let snapshots = match backup::list(store) {
+        Ok(snapshots) => snapshots,
+        Err(e) => {
+            eprintln!("❌ 读取备份目录失败: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    if snapshots.is_empty() {
+        println!("暂无备份");
+        return EXIT_OK;
+    }
+    for store in STORES {
+        let group: Vec<_> = snapshots.iter().filter(|s| s.store.file == store.file).collect();
+        if group.is_empty() {
+            continue;
+        }
+        println!("{}（最多保留 {} 份）", store.file, KEEP);
+        for snapshot in group.iter().rev() {
+            let count = std::fs::read_to_string(&snapshot.path).ok()
+                .and_then(|content| backup::entries(&content).ok())
+                .map(|entries| format!("{} 项", entries.len()))
+                .unwrap_or_else(|| String::from("无法解析"));
+            println!("  {:<28} {}  {}", snapshot.id, snapshot.time.format("%Y-%m-%d %H:%M:%S"), count);
+        }
+    }
+    EXIT_OK
+}
+
+fn restore(args: &[String]) -> i32 {
+    let args = match Args::parse(args, &[], &["yes"]) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e, USAGE),
+    };
+    let Some(id) = args.positional(0) else {
+        return usage_error("缺少要恢复的备份 ID，可通过 small_tools backup list 查看", USAGE);
+    };
+    let snapshot = match backup::find(id) {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => {
+            eprintln!("❌ 未找到备份: {}", id);
+            return EXIT_FAILURE;
+        },
+        Err(e) => {
+            eprintln!("❌ 读取备份目录失败: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    let restored = match std::fs::read_to_string(&snapshot.path) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("❌ 读取备份失败: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    let restored_entries = match backup::entries(&restored) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("❌ 备份 {} 无法解析: {}", snapshot.id, e);
+            return EXIT_FAILURE;
+        }
+    };
+
+    // 恢复期间持有锁，避免其他实例在比较和写入之间修改文件
+    let mut file = DataFile::new(snapshot.store.file, Schema::default());
+    let current = match file.lock().and_then(|_| file.read()) {
+        Ok(current) => current,
+        Err(e) => {
+            eprintln!("❌ 读取 {} 失败: {}", snapshot.store.file, e);
+            return EXIT_FAILURE;
+        }
+    };
+    let current_entries = match current.as_deref().map(backup::entries) {
+        Some(Ok(entries)) => entries,
+        Some(Err(e)) => {
+            println!("⚠️ 当前的 {} 无法解析（{}），将被整体替换", snapshot.store.file, e);
+            Vec::new()
+        },
+        None => Vec::new(),
+    };
+    if current.as_deref() == Some(restored.as_str()) {
+        println!("备份 {} 与当前内容相同，无需恢复", snapshot.id);
+        return EXIT_OK;
+    }
+
+    let diff = backup::diff(snapshot.store, &current_entries, &restored_entries);
+    println!("将用 {}（{}）恢复 {}，恢复后:",
+        snapshot.id, snapshot.time.format("%Y-%m-%d %H:%M:%S"), snapshot.store.file);
+    for (label, items) in [("新增", &diff.added), ("删除", &diff.removed), ("修改", &diff.changed)] {
+        if !items.is_empty() {
+            println!("  {} {} 项: {}", label, items.len(), items.join("、"));
+        }
+    }
+    println!("  不变 {} 项", diff.unchanged);
+
+    if !args.flag("yes") {
+        println!("确认恢复？当前内容会先保存为新的备份 (y/N)");
+        let mut input = String::new();
+        let _ = stdin().read_line(&mut input);
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("已取消");
+            return EXIT_OK;
+        }
+    }
+    if let Err(e) = file.write(&restored, false) {
+        eprintln!("❌ 恢复失败: {}", e);
+        return EXIT_FAILURE;
+    }
+    println!("✅ 已恢复 {}", snapshot.store.file);
+    if let Ok(Some(previous)) = backup::list(Some(snapshot.store)).map(|list| list.last().cloned())
+        && current.is_some() {
+        println!("恢复前的内容已备份为 {}", previous.id);
+    }
+    EXIT_OK
+}
+
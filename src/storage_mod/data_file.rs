@@ -0,0 +1,558 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::storage_mod::backup;
+use crate::storage_mod::schema::{Envelope, Schema};
+
+/// 数据文件所在的路径，如 data.json、models.json
+pub fn data_path(file_name: &str) -> PathBuf {
+    if cfg!(windows) {
+        // Windows系统使用AppData目录
+        dirs::data_local_dir().map(|mut p| {
+            p.push("SmallTool");
+            p.push(file_name);
+            p
+        }).unwrap_or_else(|| {
+            // 如果无法获取AppData目录，则使用当前目录
+            Path::new(file_name).to_path_buf()
+        })
+    } else {
+        // 非Windows系统保持原逻辑
+        dirs::data_dir().map(|mut p| {
+            p.push("small_tools");
+            p.push(file_name);
+            p
+        }).unwrap_or_else(|| {
+            // 如果无法获取数据目录，则使用当前目录
+            Path::new(file_name).to_path_buf()
+        })
+    }
+}
+
+/// 文件在读取之后被其他程序修改过
+#[derive(Debug)]
+pub struct Conflict {
+    pub path: PathBuf,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} 在读取后已被其他程序修改", self.path.display())
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// 判断保存失败是否因为文件已被其他程序修改
+pub fn is_conflict(error: &io::Error) -> bool {
+    error.get_ref().is_some_and(|e| e.is::<Conflict>())
+}
+
+/// 交互菜单中保存冲突时，由用户选择用当前内容覆盖（`overwrite`），或放弃本次修改重新加载（`reload`）
+pub fn resolve_conflict<T>(
+    data: &mut T,
+    overwrite: impl FnOnce(&mut T) -> io::Result<()>,
+    reload: impl FnOnce(&mut T) -> io::Result<()>,
+) {
+    println!("输入 o 用当前内容覆盖，其他键放弃本次修改并重新加载：");
+    let mut input = String::new();
+    let _ = std::io::stdin().read_line(&mut input);
+    if input.trim().eq_ignore_ascii_case("o") {
+        match overwrite(data) {
+            Ok(()) => println!("✅ 已覆盖保存"),
+            Err(e) => eprintln!("❌ 覆盖保存失败: {}", e),
+        }
+    } else {
+        match reload(data) {
+            Ok(()) => println!("🔄 已重新加载"),
+            Err(e) => eprintln!("❌ 重新加载失败: {}", e),
+        }
+    }
+}
+
+/// 文件内容无法解析
+#[derive(Debug)]
+pub struct Corrupt {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Corrupt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 升级旧版本数据后才发现的错误没有对应的行列号，此时 line 为 0
+        if self.line == 0 {
+            write!(f, "{} 解析失败: {}", self.path.display(), self.message)
+        } else {
+            write!(f, "{} 第 {} 行第 {} 列解析失败: {}", self.path.display(), self.line, self.column, self.message)
+        }
+    }
+}
+
+impl std::error::Error for Corrupt {}
+
+/// 判断读取失败是否因为文件内容无法解析
+pub fn is_corrupt(error: &io::Error) -> bool {
+    error.get_ref().is_some_and(|e| e.is::<Corrupt>())
+}
+
+// 文件内容的指纹，文件不存在时为 None
+fn fingerprint(content: Option<&[u8]>) -> Option<u64> {
+    content.map(|bytes| {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        hasher.finish()
+    })
+}
+
+fn read_optional(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// 读取当前版本文件时只关心 data，直接从原文解析以保留出错位置
+#[derive(Deserialize)]
+struct Stored<T> {
+    data: T,
+}
+
+/// 一个 JSON 数据文件：内容包在带版本号的外层结构中，读取旧版本时自动升级；
+/// 写入时先写临时文件再原子替换，读写期间持有同目录下 `<文件名>.lock` 的建议锁，
+/// 并在覆盖前检查文件是否在读取后被修改
+#[derive(Debug, Default)]
+pub struct DataFile {
+    path: PathBuf,
+    schema: Schema,
+    /// 最近一次读取或写入时的内容指纹
+    loaded: Option<u64>,
+    /// 通过 `lock` 持有的锁，释放前读写都不再重复加锁
+    lock: Option<File>,
+    /// 文件未能正确读取时为 true，此时拒绝写入，避免用空数据覆盖原文件
+    refuse_write: bool,
+}
+
+impl DataFile {
+    pub fn new(file_name: &str, schema: Schema) -> Self {
+        Self::at(data_path(file_name), schema)
+    }
+
+    /// 使用指定路径，用于数据目录下子目录中的文件
+    pub fn at(path: PathBuf, schema: Schema) -> Self {
+        Self { path, schema, ..Default::default() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 持有锁直到 `unlock` 或 `DataFile` 被丢弃，用于“读取—修改—保存”的整个过程
+    pub fn lock(&mut self) -> io::Result<()> {
+        if self.lock.is_none() {
+            self.lock = Some(acquire(&self.path)?);
+        }
+        Ok(())
+    }
+
+    pub fn unlock(&mut self) {
+        self.lock = None;
+    }
+
+    /// 交互菜单中开始一次修改：加锁直到 `unlock`，返回文件在上次读取或写入后是否被其他程序修改过，
+    /// 为 true 时调用方应先重新读取；出错时不保留锁
+    pub fn lock_for_edit(&mut self) -> io::Result<bool> {
+        self.lock()?;
+        match read_optional(&self.path) {
+            Ok(bytes) => Ok(fingerprint(bytes.as_deref()) != self.loaded),
+            Err(e) => {
+                self.unlock();
+                Err(e)
+            },
+        }
+    }
+
+    /// 同 `lock_for_edit`，文件被修改过时直接重新读取到 `data`；失败时提示并返回 false，不保留锁
+    pub fn begin_edit<T: DeserializeOwned + Serialize + Default>(&mut self, data: &mut T) -> bool {
+        let result = self.lock_for_edit().and_then(|stale| {
+            if stale {
+                *data = self.read_json()?.unwrap_or_default();
+                println!("🔄 {} 已被其他程序修改，已重新加载", self.path.display());
+            }
+            Ok(())
+        });
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                self.unlock();
+                eprintln!("❌ 读取数据失败: {}", e);
+                false
+            },
+        }
+    }
+
+    /// 读取文件内容并记录指纹，文件不存在时返回 None
+    pub fn read(&mut self) -> io::Result<Option<String>> {
+        let _guard = self.temporary_lock()?;
+        let bytes = read_optional(&self.path)?;
+        self.loaded = fingerprint(bytes.as_deref());
+        bytes.map(|b| String::from_utf8(b).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+            .transpose()
+    }
+
+    /// 读取并解析 JSON，旧版本的文件先升级到当前版本并备份原文件；
+    /// 解析失败时返回带行列号的 `Corrupt` 错误，并在处理前拒绝写入
+    pub fn read_json<T: DeserializeOwned + Serialize>(&mut self) -> io::Result<Option<T>> {
+        let Some(content) = self.read()? else {
+            return Ok(None);
+        };
+        let result = self.decode(&content);
+        self.refuse_write = result.is_err();
+        result.map(Some)
+    }
+
+    /// 以当前版本的外层结构写入，其余同 `write`
+    pub fn write_json<T: Serialize>(&mut self, data: &T, force: bool) -> io::Result<()> {
+        let envelope = Envelope { version: self.schema.version(), data };
+        let json = serde_json::to_string_pretty(&envelope).map_err(io::Error::other)?;
+        self.write(&json, force)
+    }
+
+    fn decode<T: DeserializeOwned + Serialize>(&mut self, content: &str) -> io::Result<T> {
+        let value: serde_json::Value = serde_json::from_str(content).map_err(|e| self.corrupt(&e))?;
+        let (version, data) = Schema::unwrap(value).map_err(|message| self.corrupt_at(0, 0, message))?;
+        if version == self.schema.version() {
+            return serde_json::from_str::<Stored<T>>(content)
+                .map(|stored| stored.data)
+                .map_err(|e| self.corrupt(&e));
+        }
+        if version > self.schema.version() {
+            // 较新版本的文件不是损坏，不能提示移走，只拒绝写入
+            return Err(io::Error::other(format!(
+                "{}: {}", self.path.display(), self.schema.upgrade(version, data).unwrap_err()
+            )));
+        }
+        let data = self.schema.upgrade(version, data).map_err(|message| self.corrupt_at(0, 0, message))?;
+        let decoded = serde_json::from_value(data).map_err(|e| self.corrupt(&e))?;
+        let backup = self.backup_path(&format!("v{}.bak", version));
+        write_atomic(&backup, content.as_bytes())?;
+        self.write_json(&decoded, false)?;
+        eprintln!(
+            "ℹ️ 已将 {} 从版本 {} 升级到版本 {}，原文件备份为 {}",
+            self.path.display(), version, self.schema.version(), backup.display()
+        );
+        Ok(decoded)
+    }
+
+    fn corrupt(&self, e: &serde_json::Error) -> io::Error {
+        // serde_json 的错误信息末尾自带 “at line L column C”，行列号已单独给出
+        let message = e.to_string();
+        let suffix = format!(" at line {} column {}", e.line(), e.column());
+        let message = message.strip_suffix(&suffix).unwrap_or(&message).to_string();
+        self.corrupt_at(e.line(), e.column(), message)
+    }
+
+    fn corrupt_at(&self, line: usize, column: usize, message: String) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, Corrupt { path: self.path.clone(), line, column, message })
+    }
+
+    // 与数据文件同目录、以 `<文件名>.<suffix>` 命名的备份，已存在时再加上时间戳
+    fn backup_path(&self, suffix: &str) -> PathBuf {
+        let with_suffix = |suffix: &str| {
+            let mut path = self.path.as_os_str().to_owned();
+            path.push(".");
+            path.push(suffix);
+            PathBuf::from(path)
+        };
+        let path = with_suffix(suffix);
+        if path.exists() {
+            with_suffix(&format!("{}-{}", suffix, chrono::Local::now().format("%Y%m%d-%H%M%S")))
+        } else {
+            path
+        }
+    }
+
+    /// 将无法解析的文件重命名为带时间戳的备份，之后可以从空数据重新开始，返回备份路径
+    pub fn set_aside(&mut self) -> io::Result<PathBuf> {
+        let _guard = self.temporary_lock()?;
+        let backup = self.backup_path(&format!("corrupt-{}", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+        fs::rename(&self.path, &backup)?;
+        self.refuse_write = false;
+        self.loaded = None;
+        Ok(backup)
+    }
+
+    /// 交互菜单中读取：文件不存在时返回空数据，无法解析时询问是否备份损坏的文件后以空数据继续，
+    /// 放弃时返回 None
+    pub fn read_or_recover<T: DeserializeOwned + Serialize + Default>(&mut self) -> Option<T> {
+        match self.read_json() {
+            Ok(data) => Some(data.unwrap_or_default()),
+            Err(e) => self.confirm_recovery(&e).then(T::default),
+        }
+    }
+
+    /// 读取失败后询问用户是否备份损坏的文件并以空数据继续，选择继续且备份成功时返回 true
+    pub fn confirm_recovery(&mut self, error: &io::Error) -> bool {
+        eprintln!("❌ 读取数据失败: {}", error);
+        if !is_corrupt(error) {
+            return false;
+        }
+        println!("为避免覆盖原有数据，在处理前不会保存任何修改。");
+        println!("输入 c 将损坏的文件备份后以空数据继续，其他键返回（可手动修复文件后重试）：");
+        let mut input = String::new();
+        let _ = std::io::stdin().read_line(&mut input);
+        if !input.trim().eq_ignore_ascii_case("c") {
+            return false;
+        }
+        match self.set_aside() {
+            Ok(backup) => {
+                println!("✅ 已将损坏的文件移动到 {}", backup.display());
+                true
+            },
+            Err(e) => {
+                eprintln!("❌ 备份损坏的文件失败: {}", e);
+                false
+            }
+        }
+    }
+
+    /// 原子地写入内容，覆盖前为原内容保存快照；文件在上次读取后被修改过时返回 `Conflict` 错误，
+    /// `force` 为 true 时跳过该检查直接覆盖
+    pub fn write(&mut self, content: &str, force: bool) -> io::Result<()> {
+        if self.refuse_write {
+            return Err(io::Error::other(format!(
+                "{} 未能正确读取，为避免覆盖原有数据已拒绝保存", self.path.display()
+            )));
+        }
+        let _guard = self.temporary_lock()?;
+        if !force {
+            let current = fingerprint(read_optional(&self.path)?.as_deref());
+            if current != self.loaded {
+                return Err(io::Error::other(Conflict { path: self.path.clone() }));
+            }
+        }
+        // 快照失败不影响保存本身
+        if let Err(e) = backup::snapshot(&self.path, content.as_bytes()) {
+            eprintln!("⚠️ 备份 {} 失败: {}", self.path.display(), e);
+        }
+        write_atomic(&self.path, content.as_bytes())?;
+        self.loaded = fingerprint(Some(content.as_bytes()));
+        Ok(())
+    }
+
+    // 未通过 lock 持有锁时，为单次读写临时加锁
+    fn temporary_lock(&self) -> io::Result<Option<File>> {
+        if self.lock.is_some() {
+            Ok(None)
+        } else {
+            acquire(&self.path).map(Some)
+        }
+    }
+}
+
+// 副本不持有锁
+impl Clone for DataFile {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            schema: self.schema,
+            loaded: self.loaded,
+            lock: None,
+            refuse_write: self.refuse_write,
+        }
+    }
+}
+
+// 锁加在单独的 .lock 文件上，因为数据文件本身会被重命名替换
+fn acquire(path: &Path) -> io::Result<File> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path)?;
+    match file.try_lock() {
+        Ok(()) => {},
+        Err(TryLockError::WouldBlock) => {
+            eprintln!("⏳ {} 正被其他实例使用，等待释放...", path.display());
+            file.lock()?;
+        },
+        Err(TryLockError::Error(e)) => return Err(e),
+    }
+    Ok(file)
+}
+
+/// 写入同目录下的临时文件并同步到磁盘后再重命名，保证目标文件要么是旧内容要么是完整的新内容
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("data");
+    let tmp = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// 测试用的空临时目录，每个测试使用不同的名称
+#[cfg(test)]
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("small_tools-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage_mod::schema;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    const V1: Schema = Schema { migrations: &[schema::wrap] };
+
+    fn corrupt_of(error: &io::Error) -> &Corrupt {
+        error.get_ref().and_then(|e| e.downcast_ref::<Corrupt>()).expect("应为 Corrupt 错误")
+    }
+
+    #[test]
+    fn writes_atomically_in_envelope() {
+        let path = temp_dir("envelope").join("list.json");
+        let mut file = DataFile::at(path.clone(), V1);
+        assert_eq!(file.read_json::<Vec<u32>>().unwrap(), None);
+        file.write_json(&vec![1, 2], false).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(value, serde_json::json!({ "version": 1, "data": [1, 2] }));
+        // 不留下临时文件
+        let names: Vec<_> = fs::read_dir(path.parent().unwrap()).unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .filter(|n| n.ends_with(".tmp"))
+            .collect();
+        assert!(names.is_empty(), "{:?}", names);
+        assert_eq!(DataFile::at(path, V1).read_json::<Vec<u32>>().unwrap(), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn detects_conflicting_write() {
+        let path = temp_dir("conflict").join("list.json");
+        let mut first = DataFile::at(path.clone(), V1);
+        first.write_json(&vec![1], false).unwrap();
+        let mut second = DataFile::at(path.clone(), V1);
+        assert_eq!(second.read_json::<Vec<u32>>().unwrap(), Some(vec![1]));
+        second.write_json(&vec![1, 2], false).unwrap();
+
+        let error = first.write_json(&vec![3], false).unwrap_err();
+        assert!(is_conflict(&error));
+        assert_eq!(second.read_json::<Vec<u32>>().unwrap(), Some(vec![1, 2]));
+        assert!(first.lock_for_edit().unwrap());
+        first.unlock();
+
+        first.write_json(&vec![3], true).unwrap();
+        assert_eq!(DataFile::at(path, V1).read_json::<Vec<u32>>().unwrap(), Some(vec![3]));
+        assert!(!first.lock_for_edit().unwrap());
+    }
+
+    #[test]
+    fn second_lock_waits_for_the_first() {
+        let path = temp_dir("lock").join("list.json");
+        let mut first = DataFile::at(path.clone(), V1);
+        first.lock().unwrap();
+
+        let mut lock_path = path.clone().into_os_string();
+        lock_path.push(".lock");
+        let other = OpenOptions::new().write(true).open(&lock_path).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+
+        let (sender, receiver) = mpsc::channel();
+        let waiter = std::thread::spawn(move || {
+            let mut second = DataFile::at(path, V1);
+            second.lock().unwrap();
+            sender.send(()).unwrap();
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(300)).is_err(), "第一个锁释放前不应拿到锁");
+        first.unlock();
+        receiver.recv_timeout(Duration::from_secs(5)).expect("第一个锁释放后应拿到锁");
+        waiter.join().unwrap();
+        assert!(other.try_lock().is_ok());
+    }
+
+    #[test]
+    fn corrupt_json_reports_line_and_column() {
+        let path = temp_dir("corrupt").join("list.json");
+        fs::write(&path, "{\n  \"version\": 1,\n  \"data\": [1, 2,,]\n}").unwrap();
+        let error = DataFile::at(path.clone(), V1).read_json::<Vec<u32>>().unwrap_err();
+        assert!(is_corrupt(&error));
+        let corrupt = corrupt_of(&error);
+        assert_eq!((corrupt.line, corrupt.column), (3, 17));
+        assert!(!corrupt.message.contains("at line"), "{}", corrupt.message);
+
+        // 结构正确但类型不符时同样给出位置
+        fs::write(&path, "{\"version\": 1, \"data\": [1, \"x\"]}").unwrap();
+        let error = DataFile::at(path, V1).read_json::<Vec<u32>>().unwrap_err();
+        assert_eq!((corrupt_of(&error).line, corrupt_of(&error).column), (1, 30));
+    }
+
+    #[test]
+    fn refuses_to_write_until_corrupt_file_is_set_aside() {
+        let dir = temp_dir("refuse");
+        let path = dir.join("list.json");
+        fs::write(&path, "not json").unwrap();
+        let mut file = DataFile::at(path.clone(), V1);
+        assert!(file.read_json::<Vec<u32>>().is_err());
+
+        // 未确认恢复时不覆盖原文件
+        let error = file.write_json(&Vec::<u32>::new(), true).unwrap_err();
+        assert!(error.to_string().contains("拒绝保存"), "{}", error);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "not json");
+
+        let backup = file.set_aside().unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "not json");
+        assert!(backup.starts_with(&dir));
+        file.write_json(&vec![7], false).unwrap();
+        assert_eq!(DataFile::at(path, V1).read_json::<Vec<u32>>().unwrap(), Some(vec![7]));
+    }
+
+    #[test]
+    fn upgrades_bare_array_and_keeps_backup() {
+        let dir = temp_dir("upgrade");
+        let path = dir.join("list.json");
+        fs::write(&path, "[1, 2, 3]").unwrap();
+        let mut file = DataFile::at(path.clone(), V1);
+        assert_eq!(file.read_json::<Vec<u32>>().unwrap(), Some(vec![1, 2, 3]));
+
+        let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(value, serde_json::json!({ "version": 1, "data": [1, 2, 3] }));
+        assert_eq!(fs::read_to_string(dir.join("list.json.v0.bak")).unwrap(), "[1, 2, 3]");
+        // 升级后的文件可以继续正常保存
+        file.write_json(&vec![4], false).unwrap();
+    }
+
+    #[test]
+    fn rejects_future_version_without_rewriting() {
+        let path = temp_dir("future").join("list.json");
+        let content = "{\"version\": 5, \"data\": [1]}";
+        fs::write(&path, content).unwrap();
+        let mut file = DataFile::at(path.clone(), V1);
+        let error = file.read_json::<Vec<u32>>().unwrap_err();
+        assert!(!is_corrupt(&error));
+        assert!(error.to_string().contains("版本为 5"), "{}", error);
+        assert!(file.write_json(&vec![2], true).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+    }
+}
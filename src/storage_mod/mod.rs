@@ -0,0 +1,4 @@
+pub mod backup;
+pub mod backup_cmd;
+pub mod data_file;
+pub mod schema;
@@ -0,0 +1,115 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// 将某一版本的数据升级到下一版本
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+/// 数据文件的外层结构：`{"version": N, "data": ...}`
+#[derive(Debug, Serialize)]
+pub struct Envelope<'a, T> {
+    pub version: u32,
+    pub data: &'a T,
+}
+
+/// 一个存储的版本信息：`migrations[i]` 把版本 i 的数据升级到版本 i + 1，
+/// 当前版本即迁移的数量；没有外层结构的旧文件视为版本 0
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Schema {
+    pub migrations: &'static [Migration],
+}
+
+impl Schema {
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+
+    /// 拆开外层结构，返回文件的版本与其中的数据
+    pub fn unwrap(value: Value) -> Result<(u32, Value), String> {
+        match value {
+            Value::Object(mut map) if map.contains_key("version") && map.contains_key("data") => {
+                let version = map.get("version")
+                    .and_then(Value::as_u64)
+                    .and_then(|v| u32::try_from(v).ok())
+                    .ok_or_else(|| String::from("version 字段不是有效的版本号"))?;
+                Ok((version, map.remove("data").unwrap_or_default()))
+            },
+            value => Ok((0, value)),
+        }
+    }
+
+    /// 依次执行迁移，把 `version` 版本的数据升级到当前版本
+    pub fn upgrade(&self, version: u32, mut data: Value) -> Result<Value, String> {
+        if version > self.version() {
+            return Err(format!(
+                "文件版本为 {}，而当前程序只支持到版本 {}，请升级程序后再打开", version, self.version()
+            ));
+        }
+        for (from, migration) in self.migrations.iter().enumerate().skip(version as usize) {
+            data = migration(data).map_err(|e| format!("从版本 {} 升级失败: {}", from, e))?;
+        }
+        Ok(data)
+    }
+}
+
+/// 版本 0 的文件只是少了外层结构，数据本身不需要改动
+pub fn wrap(data: Value) -> Result<Value, String> {
+    Ok(data)
+}
+
+/// 版本 0 的文件是 `{"<field>": [...]}` 形式的对象，取出其中的数组作为数据
+pub fn unwrap_field(mut data: Value, field: &str) -> Result<Value, String> {
+    match data.get_mut(field).map(Value::take) {
+        Some(list @ Value::Array(_)) => Ok(list),
+        Some(_) => Err(format!("{} 字段不是数组", field)),
+        None => Err(format!("缺少 {} 字段", field)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // 版本 0 → 1 包上外层结构，1 → 2 给每个条目加上 done 字段
+    const V2: Schema = Schema {
+        migrations: &[wrap, |data| match data {
+            Value::Array(items) => Ok(Value::Array(
+                items.into_iter().map(|title| json!({ "title": title, "done": false })).collect()
+            )),
+            _ => Err(String::from("不是数组")),
+        }],
+    };
+
+    #[test]
+    fn bare_data_is_version_zero() {
+        assert_eq!(Schema::unwrap(json!([1, 2])), Ok((0, json!([1, 2]))));
+        assert_eq!(Schema::unwrap(json!({ "models": [] })), Ok((0, json!({ "models": [] }))));
+        assert_eq!(Schema::unwrap(json!({ "version": 2, "data": ["a"] })), Ok((2, json!(["a"]))));
+        assert!(Schema::unwrap(json!({ "version": "x", "data": [] })).is_err());
+    }
+
+    #[test]
+    fn upgrades_through_each_migration() {
+        assert_eq!(V2.version(), 2);
+        let expected = json!([{ "title": "a", "done": false }]);
+        assert_eq!(V2.upgrade(0, json!(["a"])), Ok(expected.clone()));
+        // 已是中间版本时只执行之后的迁移
+        assert_eq!(V2.upgrade(1, json!(["a"])), Ok(expected.clone()));
+        assert_eq!(V2.upgrade(2, expected.clone()), Ok(expected));
+    }
+
+    #[test]
+    fn reports_failed_migration_and_future_version() {
+        let error = V2.upgrade(0, json!({ "a": 1 })).unwrap_err();
+        assert!(error.starts_with("从版本 1 升级失败"), "{}", error);
+        let error = V2.upgrade(3, json!([])).unwrap_err();
+        assert!(error.contains("文件版本为 3") && error.contains("只支持到版本 2"), "{}", error);
+    }
+
+    #[test]
+    fn unwraps_list_field_of_old_objects() {
+        assert_eq!(unwrap_field(json!({ "models": [{ "model_name": "m" }] }), "models"), Ok(json!([{ "model_name": "m" }])));
+        assert!(unwrap_field(json!({ "models": 1 }), "models").is_err());
+        assert!(unwrap_field(json!({}), "models").is_err());
+    }
+}
@@ -0,0 +1,367 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+
+pub const DEADLINE_HELP: &str = "\
+截止时间格式:
+  绝对时间  2026-11-03 18:00、2026-11-03（当天结束）、11-03、2026/11/03 9:30
+  相对时间  3d、2w、12h、30m，纯数字按天计算
+  自然语言  today、tomorrow 9am、next friday、friday 18:00、今天、明天 9点、后天、周五、下周一
+  只写时刻  18:00、9am、晚上8点，今天的这个时刻已过去时取明天；晚上12点指当天结束时的午夜
+  无截止    none、无、-";
+
+/// 将 "3d" / "12h" / "2w" / "30m" 转换为秒数，纯数字按天处理
+pub fn parse_duration(input: &str) -> Option<i64> {
+    let input = input.trim();
+    let (num, unit) = match input.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&input[..i], c.to_ascii_lowercase()),
+        _ => (input, 'd'),
+    };
+    let num: i64 = num.parse().ok()?;
+    let unit_secs = match unit {
+        'm' => 60,
+        'h' => 3600,
+        'd' => 24 * 3600,
+        'w' => 7 * 24 * 3600,
+        _ => return None,
+    };
+    num.checked_mul(unit_secs)
+}
+
+/// 当前时间加上偏移秒数，溢出或超出可表示的时间范围时返回 None
+pub fn offset_timestamp(now: i64, secs: i64) -> Option<i64> {
+    let ts = now.checked_add(secs)?;
+    Local.timestamp_opt(ts, 0).single().map(|_| ts)
+}
+
+/// 解析截止时间输入，返回本地时区下的时间戳；`Ok(None)` 表示无截止时间
+pub fn parse_deadline(input: &str, now: DateTime<Local>) -> Result<Option<i64>, String> {
+    let input = input.trim();
+    let lower = input.to_lowercase();
+    if matches!(lower.as_str(), "none" | "no" | "never" | "-" | "无" | "没有") {
+        return Ok(None);
+    }
+    if let Some(secs) = parse_duration(lower.trim_start_matches('+')) {
+        return offset_timestamp(now.timestamp(), secs)
+            .map(Some)
+            .ok_or_else(|| format!("截止时间超出可表示的范围: {}", input));
+    }
+    if let Some(ts) = parse_absolute(input, now) {
+        return Ok(Some(ts));
+    }
+    if let Some(ts) = parse_natural(&lower, now) {
+        return Ok(Some(ts));
+    }
+    Err(format!("无法识别的截止时间: {}", input))
+}
+
+/// 格式化截止时间，无截止时间时显示“无”
+pub fn format_deadline(dead_line: Option<i64>) -> String {
+    dead_line
+        .and_then(|t| Local.timestamp_opt(t, 0).single())
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| String::from("无"))
+}
+
+fn parse_absolute(input: &str, now: DateTime<Local>) -> Option<i64> {
+    let input = input.replace('/', "-");
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(&input, fmt) {
+            return to_local(dt);
+        }
+    }
+    // 只有日期时取当天结束
+    if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+        return end_of_day(date);
+    }
+    // 省略年份时取今年
+    let (date, time) = match input.split_once(' ') {
+        Some((d, t)) => (d, Some(t)),
+        None => (input.as_str(), None),
+    };
+    let date = NaiveDate::parse_from_str(&format!("{}-{}", now.year(), date), "%Y-%m-%d").ok()?;
+    match time {
+        Some(t) => at_time(date, t),
+        None => end_of_day(date),
+    }
+}
+
+fn parse_natural(input: &str, now: DateTime<Local>) -> Option<i64> {
+    let (day, time) = split_day_and_time(input);
+    let today = now.date_naive();
+    let date = match day {
+        "" => today,
+        "today" | "今天" | "今日" => today,
+        "tomorrow" | "tmr" | "明天" => today + Duration::days(1),
+        "后天" => today + Duration::days(2),
+        day => {
+            let (next, name) = match day.strip_prefix("next ").or_else(|| day.strip_prefix("下")) {
+                Some(name) => (true, name.trim()),
+                None => (false, day),
+            };
+            let weekday = parse_weekday(name)?;
+            let mut days_ahead = (weekday.num_days_from_monday() as i64
+                - today.weekday().num_days_from_monday() as i64)
+                .rem_euclid(7);
+            // “下周X”指下一周的周X；“next friday”指之后最近的周五，当天是周五时顺延一周；
+            // 不带修饰的 “friday” / “周五” 当天即为当天
+            if next {
+                if name.starts_with('周') || name.starts_with("星期") {
+                    days_ahead = 7 - today.weekday().num_days_from_monday() as i64
+                        + weekday.num_days_from_monday() as i64;
+                } else if days_ahead == 0 {
+                    days_ahead = 7;
+                }
+            }
+            today + Duration::days(days_ahead)
+        }
+    };
+    match time {
+        // 只给出时刻且今天的这个时刻已经过去时，取明天的同一时刻
+        Some(t) if day.is_empty() => at_time(today, t)
+            .filter(|ts| *ts > now.timestamp())
+            .or_else(|| at_time(today + Duration::days(1), t)),
+        Some(t) => at_time(date, t),
+        None if day.is_empty() => None,
+        None => end_of_day(date),
+    }
+}
+
+// 某天的某个时刻，“晚上12点”落在次日
+fn at_time(date: NaiveDate, time: &str) -> Option<i64> {
+    let (time, days) = parse_time(time)?;
+    to_local(date.checked_add_signed(Duration::days(days))?.and_time(time))
+}
+
+// 把 "tomorrow 9am" / "明天9点" 拆成日期部分和时间部分
+fn split_day_and_time(input: &str) -> (&str, Option<&str>) {
+    if let Some((day, time)) = input.rsplit_once(' ')
+        && parse_time(time).is_some() {
+        return (day.trim(), Some(time));
+    }
+    if parse_time(input).is_some() {
+        return ("", Some(input));
+    }
+    for prefix in ["今天", "今日", "明天", "后天"] {
+        if let Some(time) = input.strip_prefix(prefix)
+            && !time.is_empty() && parse_time(time).is_some() {
+            return (prefix, Some(time));
+        }
+    }
+    (input, None)
+}
+
+/// 解析 "18:00"、"9:30:15"、"9am"、"9:30pm"、"9点"、"9点30"，返回时刻及相对当天的天数；
+/// “晚上12点”指当天结束时的午夜，即次日 0 点
+fn parse_time(input: &str) -> Option<(NaiveTime, i64)> {
+    let input = input.trim().to_lowercase();
+    if let Ok(t) = NaiveTime::parse_from_str(&input, "%H:%M:%S") {
+        return Some((t, 0));
+    }
+    if let Ok(t) = NaiveTime::parse_from_str(&input, "%H:%M") {
+        return Some((t, 0));
+    }
+    let night = input.starts_with("晚上");
+    let (body, offset) = if let Some(b) = input.strip_suffix("am") {
+        (b, Some(0))
+    } else if let Some(b) = input.strip_suffix("pm") {
+        (b, Some(12))
+    } else if let Some(b) = input.strip_prefix("上午") {
+        (b, Some(0))
+    } else if let Some(b) = input.strip_prefix("下午").or_else(|| input.strip_prefix("晚上")) {
+        (b, Some(12))
+    } else {
+        (input.as_str(), None)
+    };
+    let body = body.trim().trim_end_matches('分');
+    let (hour, minute) = match body.split_once(':').or_else(|| body.split_once('点')) {
+        Some((h, m)) => (h, if m.is_empty() { "0" } else { m }),
+        None if offset.is_some() => (body, "0"),
+        None => return None,
+    };
+    let mut hour: u32 = hour.parse().ok()?;
+    let minute: u32 = minute.parse().ok()?;
+    let mut days = 0;
+    if let Some(offset) = offset {
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        if night && hour == 12 {
+            hour = 0;
+            days = 1;
+        } else {
+            hour = hour % 12 + offset;
+        }
+    }
+    NaiveTime::from_hms_opt(hour, minute, 0).map(|t| (t, days))
+}
+
+fn parse_weekday(input: &str) -> Option<Weekday> {
+    let input = input.trim()
+        .trim_start_matches("星期")
+        .trim_start_matches('周');
+    match input {
+        "mon" | "monday" | "一" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" | "二" => Some(Weekday::Tue),
+        "wed" | "wednesday" | "三" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" | "四" => Some(Weekday::Thu),
+        "fri" | "friday" | "五" => Some(Weekday::Fri),
+        "sat" | "saturday" | "六" => Some(Weekday::Sat),
+        "sun" | "sunday" | "日" | "天" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+// 夏令时切换导致本地时间有歧义时取较早的时刻
+fn to_local(dt: NaiveDateTime) -> Option<i64> {
+    Local.from_local_datetime(&dt).earliest().map(|t| t.timestamp())
+}
+
+fn end_of_day(date: NaiveDate) -> Option<i64> {
+    to_local(date.and_hms_opt(23, 59, 59)?)
+}
+
+/// 将秒数格式化为“2天5小时”“3小时20分钟”“15分钟”，只保留最大的两个单位
+pub fn format_span(secs: i64) -> String {
+    let secs = secs.abs();
+    let (days, hours, minutes) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        if hours > 0 { format!("{}天{}小时", days, hours) } else { format!("{}天", days) }
+    } else if hours > 0 {
+        if minutes > 0 { format!("{}小时{}分钟", hours, minutes) } else { format!("{}小时", hours) }
+    } else {
+        format!("{}分钟", minutes.max(1))
+    }
+}
+
+/// 相对当前时间描述截止时间，如“已逾期 2天”“剩余 5小时”
+pub fn describe_remaining(dead_line: i64, now: i64) -> String {
+    if dead_line < now {
+        format!("已逾期 {}", format_span(now - dead_line))
+    } else {
+        format!("剩余 {}", format_span(dead_line - now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-10-14 是周三
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 10, 14, 10, 30, 0).unwrap()
+    }
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> Option<i64> {
+        Some(Local.with_ymd_and_hms(y, m, d, h, min, s).unwrap().timestamp())
+    }
+
+    fn time(h: u32, m: u32) -> Option<(NaiveTime, i64)> {
+        NaiveTime::from_hms_opt(h, m, 0).map(|t| (t, 0))
+    }
+
+    #[test]
+    fn relative() {
+        let base = now().timestamp();
+        assert_eq!(parse_deadline("3d", now()), Ok(Some(base + 3 * 86400)));
+        assert_eq!(parse_deadline("+12H", now()), Ok(Some(base + 12 * 3600)));
+        assert_eq!(parse_deadline("2w", now()), Ok(Some(base + 14 * 86400)));
+        assert_eq!(parse_deadline("30m", now()), Ok(Some(base + 1800)));
+        assert_eq!(parse_deadline("2", now()), Ok(Some(base + 2 * 86400)));
+        assert_eq!(parse_duration("3y"), None);
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        assert!(parse_deadline("153722867280912930m", now()).is_err());
+        assert!(parse_deadline("9999999999999d", now()).is_err());
+        assert!(parse_deadline("99999999999999999999d", now()).is_err());
+        assert_eq!(offset_timestamp(i64::MAX, 1), None);
+    }
+
+    #[test]
+    fn none() {
+        for input in ["none", "-", "无", " NEVER "] {
+            assert_eq!(parse_deadline(input, now()), Ok(None));
+        }
+        assert!(parse_deadline("someday", now()).is_err());
+    }
+
+    #[test]
+    fn absolute() {
+        assert_eq!(parse_deadline("2026-11-03 18:00", now()), Ok(at(2026, 11, 3, 18, 0, 0)));
+        assert_eq!(parse_deadline("2026/11/03 9:30", now()), Ok(at(2026, 11, 3, 9, 30, 0)));
+        assert_eq!(parse_deadline("2026-11-03T08:15:30", now()), Ok(at(2026, 11, 3, 8, 15, 30)));
+        // 只有日期时取当天结束，省略年份时取今年
+        assert_eq!(parse_deadline("2026-11-03", now()), Ok(at(2026, 11, 3, 23, 59, 59)));
+        assert_eq!(parse_deadline("11-03", now()), Ok(at(2026, 11, 3, 23, 59, 59)));
+        assert_eq!(parse_deadline("12-25 9am", now()), Ok(at(2026, 12, 25, 9, 0, 0)));
+        assert!(parse_deadline("2026-02-30", now()).is_err());
+    }
+
+    #[test]
+    fn natural_days() {
+        assert_eq!(parse_natural("today", now()), at(2026, 10, 14, 23, 59, 59));
+        assert_eq!(parse_natural("tomorrow 9am", now()), at(2026, 10, 15, 9, 0, 0));
+        assert_eq!(parse_natural("18:00", now()), at(2026, 10, 14, 18, 0, 0));
+        assert_eq!(parse_natural("明天9点", now()), at(2026, 10, 15, 9, 0, 0));
+        assert_eq!(parse_natural("后天", now()), at(2026, 10, 16, 23, 59, 59));
+        assert_eq!(parse_natural("今天 下午3点30", now()), at(2026, 10, 14, 15, 30, 0));
+        assert_eq!(parse_natural("yesterday", now()), None);
+    }
+
+    #[test]
+    fn midnight_at_night_is_next_day() {
+        assert_eq!(parse_natural("晚上12点", now()), at(2026, 10, 15, 0, 0, 0));
+        assert_eq!(parse_natural("今天晚上12点", now()), at(2026, 10, 15, 0, 0, 0));
+        assert_eq!(parse_natural("明天 晚上12点30", now()), at(2026, 10, 16, 0, 30, 0));
+        assert_eq!(parse_natural("周五 晚上11点", now()), at(2026, 10, 16, 23, 0, 0));
+        assert_eq!(parse_deadline("12-31 晚上12点", now()), Ok(at(2027, 1, 1, 0, 0, 0)));
+    }
+
+    #[test]
+    fn past_time_of_day_rolls_to_tomorrow() {
+        // 当前是 10:30
+        assert_eq!(parse_natural("9:00", now()), at(2026, 10, 15, 9, 0, 0));
+        assert_eq!(parse_natural("10:30", now()), at(2026, 10, 15, 10, 30, 0));
+        assert_eq!(parse_natural("10:31", now()), at(2026, 10, 14, 10, 31, 0));
+        assert_eq!(parse_natural("上午8点", now()), at(2026, 10, 15, 8, 0, 0));
+        // 明确指定今天时不顺延
+        assert_eq!(parse_natural("today 9am", now()), at(2026, 10, 14, 9, 0, 0));
+        assert_eq!(parse_natural("今天9点", now()), at(2026, 10, 14, 9, 0, 0));
+    }
+
+    #[test]
+    fn natural_weekdays() {
+        // 当天是周三
+        assert_eq!(parse_natural("friday 18:00", now()), at(2026, 10, 16, 18, 0, 0));
+        assert_eq!(parse_natural("wednesday", now()), at(2026, 10, 14, 23, 59, 59));
+        assert_eq!(parse_natural("next wednesday", now()), at(2026, 10, 21, 23, 59, 59));
+        assert_eq!(parse_natural("next fri", now()), at(2026, 10, 16, 23, 59, 59));
+        assert_eq!(parse_natural("mon", now()), at(2026, 10, 19, 23, 59, 59));
+        assert_eq!(parse_natural("周五", now()), at(2026, 10, 16, 23, 59, 59));
+        assert_eq!(parse_natural("星期日", now()), at(2026, 10, 18, 23, 59, 59));
+        // 下周X 指下一周的周X，即使本周的周X 还没到
+        assert_eq!(parse_natural("下周五", now()), at(2026, 10, 23, 23, 59, 59));
+        assert_eq!(parse_natural("下周一 9:00", now()), at(2026, 10, 19, 9, 0, 0));
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(parse_time("18:00"), time(18, 0));
+        assert_eq!(parse_time("9:30:15"), NaiveTime::from_hms_opt(9, 30, 15).map(|t| (t, 0)));
+        assert_eq!(parse_time("9am"), time(9, 0));
+        assert_eq!(parse_time("9:30PM"), time(21, 30));
+        assert_eq!(parse_time("12am"), time(0, 0));
+        assert_eq!(parse_time("12pm"), time(12, 0));
+        assert_eq!(parse_time("13pm"), None);
+        assert_eq!(parse_time("0am"), None);
+        assert_eq!(parse_time("9点"), time(9, 0));
+        assert_eq!(parse_time("9点30分"), time(9, 30));
+        assert_eq!(parse_time("下午3点"), time(15, 0));
+        assert_eq!(parse_time("上午12点"), time(0, 0));
+        assert_eq!(parse_time("下午12点"), time(12, 0));
+        assert_eq!(parse_time("晚上8点"), time(20, 0));
+        assert_eq!(parse_time("晚上12点"), NaiveTime::from_hms_opt(0, 0, 0).map(|t| (t, 1)));
+        assert_eq!(parse_time("25:00"), None);
+        assert_eq!(parse_time("9"), None);
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use crate::todo_mod::todo_list::{Todo, Todos};
+
+/// 返回尚未完成的依赖项；依赖的事项已被删除时视为已满足
+pub fn open_dependencies<'a>(todos: &'a [Todo], todo: &Todo) -> Vec<&'a Todo> {
+    todo.depends_on.iter()
+        .filter_map(|id| todos.iter().find(|t| t.id.eq_ignore_ascii_case(id)))
+        .filter(|t| !t.status.is_closed())
+        .collect()
+}
+
+/// 未关闭且仍有未完成依赖的事项视为被阻塞
+pub fn is_blocked(todos: &[Todo], todo: &Todo) -> bool {
+    !todo.status.is_closed() && !open_dependencies(todos, todo).is_empty()
+}
+
+/// 沿依赖关系从 from 出发能否到达 target
+pub fn reaches(todos: &[Todo], from: &str, target: &str) -> bool {
+    let mut stack = vec![from.to_string()];
+    let mut visited = HashSet::new();
+    while let Some(id) = stack.pop() {
+        if id.eq_ignore_ascii_case(target) {
+            return true;
+        }
+        if !visited.insert(id.to_lowercase()) {
+            continue;
+        }
+        if let Some(todo) = todos.iter().find(|t| t.id.eq_ignore_ascii_case(&id)) {
+            stack.extend(todo.depends_on.iter().cloned());
+        }
+    }
+    false
+}
+
+impl Todos {
+    /// 将以逗号或空白分隔的 ID/序号解析为依赖 ID 列表，`index` 为设置依赖的事项，
+    /// 新事项尚未加入列表时传 None；依赖自身或形成循环时返回错误
+    pub fn parse_dependencies(&self, index: Option<usize>, input: &str) -> Result<Vec<String>, String> {
+        let mut ids: Vec<String> = Vec::new();
+        for key in input.split(|c: char| c == ',' || c == '，' || c.is_whitespace()) {
+            if key.trim().is_empty() {
+                continue;
+            }
+            let dep = self.resolve(key)?;
+            if Some(dep) == index {
+                return Err(String::from("事项不能依赖自身"));
+            }
+            let id = &self.todos[dep].id;
+            if let Some(index) = index
+                && reaches(&self.todos, id, &self.todos[index].id) {
+                return Err(format!("依赖 '{}' ({}) 会形成循环依赖", self.todos[dep].title, id));
+            }
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+        Ok(ids)
+    }
+
+    /// 返回阻塞该事项的未完成依赖项
+    pub fn blockers(&self, index: usize) -> Vec<&Todo> {
+        open_dependencies(&self.todos, &self.todos[index])
+    }
+
+    pub fn is_blocked(&self, index: usize) -> bool {
+        is_blocked(&self.todos, &self.todos[index])
+    }
+}
+
+/// 将依赖项格式化为“标题 (ID)”列表
+pub fn describe(todos: &[&Todo]) -> String {
+    todos.iter()
+        .map(|t| format!("{} ({})", t.title, t.id))
+        .collect::<Vec<_>>()
+        .join("、")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo_mod::todo_list::Status;
+
+    fn todos(items: &[(&str, Status, &[&str])]) -> Todos {
+        let mut todos = Todos::default();
+        for (id, status, deps) in items {
+            todos.todos.push(Todo {
+                id: id.to_string(),
+                title: format!("事项{}", id),
+                status: *status,
+                depends_on: deps.iter().map(|d| d.to_string()).collect(),
+                ..Default::default()
+            });
+        }
+        todos
+    }
+
+    #[test]
+    fn only_open_existing_dependencies_block() {
+        let todos = todos(&[
+            ("a", Status::Pending, &[]),
+            ("b", Status::Done, &[]),
+            ("c", Status::Cancelled, &[]),
+            ("d", Status::InProgress, &["A", "b", "c", "gone"]),
+            ("e", Status::Pending, &["b", "gone"]),
+            ("f", Status::Done, &["a"]),
+        ]);
+        assert_eq!(todos.blockers(3).iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), ["a"]);
+        assert!(todos.is_blocked(3));
+        // 依赖已完成、已取消或已删除时不阻塞
+        assert!(!todos.is_blocked(4));
+        // 已完成的事项不算被阻塞
+        assert!(!todos.is_blocked(5));
+        assert_eq!(describe(&todos.blockers(3)), "事项a (a)");
+    }
+
+    #[test]
+    fn reaches_follows_chains_and_stops_on_cycles() {
+        let todos = todos(&[
+            ("a", Status::Pending, &["b"]),
+            ("b", Status::Pending, &["C"]),
+            ("c", Status::Pending, &["a", "missing"]),
+            ("d", Status::Pending, &[]),
+        ]);
+        assert!(reaches(&todos.todos, "a", "c"));
+        assert!(reaches(&todos.todos, "c", "b"));
+        assert!(reaches(&todos.todos, "A", "a"));
+        assert!(!reaches(&todos.todos, "a", "d"));
+        assert!(!reaches(&todos.todos, "d", "a"));
+        assert!(!reaches(&todos.todos, "missing", "a"));
+    }
+
+    #[test]
+    fn parses_dependency_list() {
+        let todos = todos(&[
+            ("a", Status::Pending, &[]),
+            ("b", Status::Pending, &["a"]),
+            ("c", Status::Done, &[]),
+        ]);
+        assert_eq!(todos.parse_dependencies(Some(2), "A, 2，b  1"), Ok(vec![String::from("a"), String::from("b")]));
+        assert_eq!(todos.parse_dependencies(Some(0), " "), Ok(Vec::new()));
+        assert_eq!(todos.parse_dependencies(Some(0), "c"), Ok(vec![String::from("c")]));
+        assert_eq!(todos.parse_dependencies(Some(1), "b"), Err(String::from("事项不能依赖自身")));
+        assert!(todos.parse_dependencies(Some(0), "b").unwrap_err().contains("循环依赖"));
+        assert!(todos.parse_dependencies(Some(0), "zzz").is_err());
+        assert!(todos.parse_dependencies(Some(0), "9").is_err());
+        // 新事项还不在列表中，不会形成循环
+        assert_eq!(todos.parse_dependencies(None, "b,a"), Ok(vec![String::from("b"), String::from("a")]));
+    }
+}
@@ -0,0 +1,201 @@
+use chrono::TimeZone;
+
+use crate::todo_mod::ical;
+use crate::todo_mod::todo_list::{Priority, Status};
+use crate::todo_mod::view::Entry;
+
+/// CSV 的列，导入时按同样的列名识别
+pub const CSV_HEADER: [&str; 13] = [
+    "id", "title", "content", "status", "priority", "project", "tags",
+    "due", "created", "updated", "completed", "depends_on", "subtasks",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Markdown,
+    TodoTxt,
+    /// iCalendar，`events` 为 true 时为截止时间额外生成日历事件
+    Ics { events: bool },
+}
+
+impl ExportFormat {
+    pub fn parse(input: &str) -> Option<ExportFormat> {
+        match input.trim().to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "txt" | "todotxt" | "todo.txt" => Some(ExportFormat::TodoTxt),
+            "ics" | "ical" | "icalendar" => Some(ExportFormat::Ics { events: false }),
+            _ => None,
+        }
+    }
+
+    /// 根据输出文件的扩展名推断格式
+    pub fn from_path(path: &str) -> Option<ExportFormat> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?;
+        ExportFormat::parse(ext)
+    }
+
+    pub fn render(&self, entries: &[Entry]) -> Result<String, String> {
+        match self {
+            ExportFormat::Csv => to_csv(entries),
+            ExportFormat::Markdown => Ok(to_markdown(entries)),
+            ExportFormat::TodoTxt => Ok(to_todo_txt(entries)),
+            ExportFormat::Ics { events } => Ok(ical::to_ics(entries, *events)),
+        }
+    }
+}
+
+fn format_time(ts: Option<i64>, fmt: &str) -> String {
+    ts.and_then(|t| chrono::Local.timestamp_opt(t, 0).single())
+        .map(|t| t.format(fmt).to_string())
+        .unwrap_or_default()
+}
+
+const DATE_TIME: &str = "%Y-%m-%d %H:%M:%S";
+
+fn to_csv(entries: &[Entry]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let to_string = |e: csv::Error| e.to_string();
+    writer.write_record(CSV_HEADER).map_err(to_string)?;
+    for (_, t) in entries {
+        // 检查项按行拼接，已勾选的以 [x] 开头
+        let subtasks = t.subtasks.iter()
+            .map(|s| format!("[{}] {}", if s.done { "x" } else { " " }, s.text))
+            .collect::<Vec<_>>()
+            .join("\n");
+        writer.write_record([
+            t.id.as_str(),
+            &t.title,
+            &t.content,
+            status_key(t.status),
+            t.priority.label(),
+            t.project.as_deref().unwrap_or_default(),
+            &t.tags.join(","),
+            &format_time(t.dead_line, DATE_TIME),
+            &format_time(Some(t.create_time), DATE_TIME),
+            &format_time(t.updated_time, DATE_TIME),
+            &format_time(t.completed_time, DATE_TIME),
+            &t.depends_on.join(","),
+            &subtasks,
+        ]).map_err(to_string)?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+/// 与 `Status::parse` 对应的英文名称
+pub fn status_key(status: Status) -> &'static str {
+    match status {
+        Status::Pending => "pending",
+        Status::InProgress => "in-progress",
+        Status::Done => "done",
+        Status::Cancelled => "cancelled",
+    }
+}
+
+fn to_markdown(entries: &[Entry]) -> String {
+    let mut out = format!(
+        "# 待办事项\n\n导出时间: {}\n\n",
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    );
+    for (_, t) in entries {
+        let title = single_line(&t.title);
+        let title = if t.status == Status::Cancelled { format!("~~{}~~", title) } else { title };
+        let mut line = format!(
+            "- [{}] {} `{}`",
+            if t.status.is_closed() { "x" } else { " " },
+            title,
+            t.priority.label()
+        );
+        if t.status == Status::InProgress {
+            line.push_str(" *进行中*");
+        }
+        if let Some(project) = &t.project {
+            line.push_str(&format!(" 📁 {}", project));
+        }
+        for tag in &t.tags {
+            line.push_str(&format!(" #{}", tag));
+        }
+        if t.dead_line.is_some() {
+            line.push_str(&format!(" · 截止 {}", format_time(t.dead_line, "%Y-%m-%d %H:%M")));
+        }
+        if let Some((done, total)) = t.progress() {
+            line.push_str(&format!(" [{}/{}]", done, total));
+        }
+        out.push_str(&line);
+        out.push('\n');
+        for subtask in &t.subtasks {
+            out.push_str(&format!("  - [{}] {}\n", if subtask.done { "x" } else { " " }, single_line(&subtask.text)));
+        }
+    }
+    out
+}
+
+// todo.txt 的优先级为 (A)-(Z)，P0 对应 (A)
+pub fn todo_txt_priority(priority: Priority) -> char {
+    match priority {
+        Priority::P0 => 'A',
+        Priority::P1 => 'B',
+        Priority::P2 => 'C',
+        Priority::P3 => 'D',
+    }
+}
+
+// todo.txt 中的项目和上下文不能包含空白
+fn token(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 每行一条：`x 完成日期 创建日期 (优先级) 标题 +项目 @标签 due:日期 id:ID`；
+/// 已完成的事项按惯例不写优先级，改用 pri: 保留；按 todo.txt 的规定，
+/// 已完成的事项只有写了完成日期才能写创建日期，否则唯一的日期会被当作完成日期
+fn to_todo_txt(entries: &[Entry]) -> String {
+    let mut out = String::new();
+    for (_, t) in entries {
+        let mut parts: Vec<String> = Vec::new();
+        let priority = todo_txt_priority(t.priority);
+        let mut dated = true;
+        if t.status.is_closed() {
+            parts.push(String::from("x"));
+            let completed = format_time(t.completed_time, "%Y-%m-%d");
+            dated = !completed.is_empty();
+            if dated {
+                parts.push(completed);
+            }
+        } else {
+            parts.push(format!("({})", priority));
+        }
+        if dated {
+            parts.push(format_time(Some(t.create_time), "%Y-%m-%d"));
+        }
+        parts.push(single_line(&t.title));
+        if let Some(project) = &t.project {
+            parts.push(format!("+{}", token(project)));
+        }
+        parts.extend(t.tags.iter().map(|tag| format!("@{}", token(tag))));
+        if t.dead_line.is_some() {
+            parts.push(format!("due:{}", format_time(t.dead_line, "%Y-%m-%d")));
+        }
+        if t.status.is_closed() {
+            parts.push(format!("pri:{}", priority));
+        }
+        match t.status {
+            Status::InProgress | Status::Cancelled => parts.push(format!("status:{}", status_key(t.status))),
+            _ => {},
+        }
+        if !t.id.is_empty() {
+            parts.push(format!("id:{}", t.id));
+        }
+        if !t.depends_on.is_empty() {
+            parts.push(format!("dep:{}", t.depends_on.join(",")));
+        }
+        out.push_str(&parts.join(" "));
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,314 @@
+use regex::{Regex, RegexBuilder};
+
+use crate::todo_mod::deadline::{offset_timestamp, parse_duration};
+use crate::todo_mod::todo_list::{Priority, Status, Todo};
+
+pub const FILTER_HELP: &str = "\
+筛选表达式由空格分隔的条件组成，所有条件需同时满足，条件前加 - 表示取反:
+  关键字            标题或内容包含该关键字（不区分大小写），含空格时用引号包裹
+  status:<状态>     pending/in-progress/done/cancelled，open 表示未关闭，closed 表示已关闭
+  priority<=P1      按优先级比较，支持 : = < <= > >=
+  tag:<标签>        带有该标签
+  project:<项目>    属于该项目
+  title:<文本>      标题包含文本
+  due<7d            截止时间早于 7 天后，due:7d 等同 due<=7d，due<0 表示已过期
+示例: status:pending due<7d tag:bug -project:archive";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cmp {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Cmp {
+    fn test<T: Ord>(&self, left: T, right: T) -> bool {
+        match self {
+            Cmp::Lt => left < right,
+            Cmp::Le => left <= right,
+            Cmp::Eq => left == right,
+            Cmp::Ge => left >= right,
+            Cmp::Gt => left > right,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Text(String),
+    Regex(Regex),
+    Title(String),
+    Status(Vec<Status>),
+    Priority(Cmp, Priority),
+    Tag(String),
+    Project(String),
+    /// 截止时间与“当前时间 + 偏移秒数”比较，没有截止时间的事项不匹配
+    Due(Cmp, i64),
+}
+
+impl Term {
+    fn matches(&self, todo: &Todo, now: i64) -> bool {
+        match self {
+            Term::Text(text) => contains_ignore_case(&todo.title, text)
+                || contains_ignore_case(&todo.content, text),
+            Term::Regex(re) => re.is_match(&todo.title) || re.is_match(&todo.content),
+            Term::Title(text) => contains_ignore_case(&todo.title, text),
+            Term::Status(statuses) => statuses.contains(&todo.status),
+            Term::Priority(cmp, priority) => cmp.test(todo.priority, *priority),
+            Term::Tag(tag) => todo.has_tag(tag),
+            Term::Project(project) => todo.project.as_deref()
+                .is_some_and(|p| p.eq_ignore_ascii_case(project)),
+            Term::Due(cmp, offset) => todo.dead_line
+                .is_some_and(|d| cmp.test(d, now.saturating_add(*offset))),
+        }
+    }
+}
+
+/// 搜索/筛选条件，交互菜单和命令行共用
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    terms: Vec<(bool, Term)>,
+}
+
+impl Filter {
+    /// 解析筛选表达式，如 `status:pending due<7d tag:bug`
+    pub fn parse(input: &str) -> Result<Filter, String> {
+        let mut filter = Filter::default();
+        for token in tokenize(input)? {
+            let (negated, token) = match token.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest.to_string()),
+                _ => (false, token),
+            };
+            filter.terms.push((negated, parse_term(&token)?));
+        }
+        Ok(filter)
+    }
+
+    /// 按标题/内容搜索，`regex` 为 true 时按正则表达式匹配（不区分大小写）
+    pub fn search(pattern: &str, regex: bool) -> Result<Filter, String> {
+        let term = if regex {
+            let re = RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("无效的正则表达式: {}", e))?;
+            Term::Regex(re)
+        } else {
+            Term::Text(pattern.to_string())
+        };
+        Ok(Filter { terms: vec![(false, term)] })
+    }
+
+    /// 合并两个筛选条件，结果需同时满足两者
+    pub fn and(mut self, other: Filter) -> Filter {
+        self.terms.extend(other.terms);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// 显式指定了状态条件时，不再默认隐藏已完成/已取消的事项
+    pub fn mentions_status(&self) -> bool {
+        self.terms.iter().any(|(_, t)| matches!(t, Term::Status(_)))
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        let now = chrono::Local::now().timestamp();
+        self.terms.iter().all(|(negated, term)| term.matches(todo, now) != *negated)
+    }
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+// 按空白切分，双引号内的空白保留
+fn tokenize(input: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in input.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            },
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(String::from("引号未闭合"));
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+fn parse_term(token: &str) -> Result<Term, String> {
+    let Some(pos) = token.find([':', '<', '>', '=']) else {
+        return Ok(Term::Text(token.to_string()));
+    };
+    let key = token[..pos].to_lowercase();
+    let rest = &token[pos..];
+    let (cmp, value) = if let Some(v) = rest.strip_prefix("<=") {
+        (Cmp::Le, v)
+    } else if let Some(v) = rest.strip_prefix(">=") {
+        (Cmp::Ge, v)
+    } else if let Some(v) = rest.strip_prefix('<') {
+        (Cmp::Lt, v)
+    } else if let Some(v) = rest.strip_prefix('>') {
+        (Cmp::Gt, v)
+    } else {
+        (Cmp::Eq, &rest[1..])
+    };
+    if value.is_empty() {
+        return Err(format!("条件 '{}' 缺少取值", token));
+    }
+    let only_eq = |term: Term| {
+        if cmp == Cmp::Eq {
+            Ok(term)
+        } else {
+            Err(format!("条件 '{}' 不支持大小比较", token))
+        }
+    };
+
+    match key.as_str() {
+        "status" | "is" => {
+            let statuses = match value.to_lowercase().as_str() {
+                "open" => vec![Status::Pending, Status::InProgress],
+                "closed" => vec![Status::Done, Status::Cancelled],
+                v => vec![Status::parse(v).ok_or_else(|| format!("无效的状态: {}", value))?],
+            };
+            only_eq(Term::Status(statuses))
+        },
+        "priority" | "pri" | "p" => {
+            let priority = Priority::parse(value)
+                .ok_or_else(|| format!("无效的优先级: {}", value))?;
+            Ok(Term::Priority(cmp, priority))
+        },
+        "due" | "deadline" => {
+            let offset = parse_duration(value)
+                .ok_or_else(|| format!("无效的时间范围: {}", value))?;
+            if offset_timestamp(chrono::Local::now().timestamp(), offset).is_none() {
+                return Err(format!("时间范围超出可表示的范围: {}", value));
+            }
+            // 对时间戳做相等比较没有意义，due:7d 按“7 天内到期”处理
+            let cmp = if cmp == Cmp::Eq { Cmp::Le } else { cmp };
+            Ok(Term::Due(cmp, offset))
+        },
+        "tag" => only_eq(Term::Tag(value.trim_start_matches('#').to_string())),
+        "project" => only_eq(Term::Project(value.to_string())),
+        "title" => only_eq(Term::Title(value.to_string())),
+        // 未知前缀按普通关键字处理，避免误伤包含冒号的文本
+        _ => Ok(Term::Text(token.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(title: &str, status: Status, priority: Priority, dead_line: Option<i64>) -> Todo {
+        Todo {
+            title: title.to_string(),
+            status,
+            priority,
+            dead_line,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tokenize_keeps_quoted_whitespace() {
+        assert_eq!(tokenize("  a  \"b c\"\tti\"tle:x y\" ").unwrap(), ["a", "b c", "title:x y"]);
+        assert_eq!(tokenize("").unwrap(), Vec::<String>::new());
+        assert!(tokenize("\"open").is_err());
+    }
+
+    #[test]
+    fn parse_term_comparisons() {
+        assert!(matches!(parse_term("priority<=P1"), Ok(Term::Priority(Cmp::Le, Priority::P1))));
+        assert!(matches!(parse_term("p>=2"), Ok(Term::Priority(Cmp::Ge, Priority::P2))));
+        assert!(matches!(parse_term("pri<p3"), Ok(Term::Priority(Cmp::Lt, Priority::P3))));
+        assert!(matches!(parse_term("p=0"), Ok(Term::Priority(Cmp::Eq, Priority::P0))));
+        assert!(matches!(parse_term("due<7d"), Ok(Term::Due(Cmp::Lt, 604800))));
+        // due:7d 按 7 天内到期处理
+        assert!(matches!(parse_term("due:7d"), Ok(Term::Due(Cmp::Le, 604800))));
+        assert!(matches!(parse_term("due>12h"), Ok(Term::Due(Cmp::Gt, 43200))));
+    }
+
+    #[test]
+    fn parse_term_values() {
+        assert!(matches!(parse_term("status:open"), Ok(Term::Status(s)) if s == [Status::Pending, Status::InProgress]));
+        assert!(matches!(parse_term("is:closed"), Ok(Term::Status(s)) if s == [Status::Done, Status::Cancelled]));
+        assert!(matches!(parse_term("tag:#bug"), Ok(Term::Tag(t)) if t == "bug"));
+        assert!(matches!(parse_term("project:release"), Ok(Term::Project(p)) if p == "release"));
+        assert!(matches!(parse_term("周报"), Ok(Term::Text(t)) if t == "周报"));
+        // 未知前缀按普通关键字处理
+        assert!(matches!(parse_term("http://x"), Ok(Term::Text(t)) if t == "http://x"));
+    }
+
+    #[test]
+    fn parse_term_errors() {
+        assert!(parse_term("status:").is_err());
+        assert!(parse_term("status:unknown").is_err());
+        assert!(parse_term("status<done").is_err());
+        assert!(parse_term("tag>x").is_err());
+        assert!(parse_term("priority:P9").is_err());
+        assert!(parse_term("due<soon").is_err());
+        assert!(parse_term("due<153722867280912930m").is_err());
+        assert!(parse_term("due<9999999999999d").is_err());
+    }
+
+    #[test]
+    fn negation() {
+        let done = todo("写周报", Status::Done, Priority::P2, None);
+        let open = todo("写周报", Status::Pending, Priority::P2, None);
+        let filter = Filter::parse("-status:done").unwrap();
+        assert!(!filter.matches(&done));
+        assert!(filter.matches(&open));
+        // 单独的 - 不是取反，而是关键字
+        let filter = Filter::parse("-").unwrap();
+        assert!(matches!(&filter.terms[..], [(false, Term::Text(t))] if t == "-"));
+    }
+
+    #[test]
+    fn negation_binds_to_single_term_and_terms_are_anded() {
+        let filter = Filter::parse("周报 -priority<=P1 status:open").unwrap();
+        assert_eq!(filter.terms.len(), 3);
+        assert!(filter.matches(&todo("写周报", Status::Pending, Priority::P2, None)));
+        assert!(!filter.matches(&todo("写周报", Status::Pending, Priority::P1, None)));
+        assert!(!filter.matches(&todo("写周报", Status::Done, Priority::P2, None)));
+        assert!(!filter.matches(&todo("写日报", Status::Pending, Priority::P2, None)));
+    }
+
+    #[test]
+    fn due_matches() {
+        let now = chrono::Local::now().timestamp();
+        let overdue = todo("a", Status::Pending, Priority::P2, Some(now - 3600));
+        let soon = todo("b", Status::Pending, Priority::P2, Some(now + 3600));
+        let none = todo("c", Status::Pending, Priority::P2, None);
+        let filter = Filter::parse("due<0").unwrap();
+        assert!(filter.matches(&overdue));
+        assert!(!filter.matches(&soon));
+        assert!(!filter.matches(&none));
+        let filter = Filter::parse("due:1d").unwrap();
+        assert!(filter.matches(&overdue) && filter.matches(&soon) && !filter.matches(&none));
+        // 没有截止时间的事项取反后匹配
+        assert!(Filter::parse("-due:1d").unwrap().matches(&none));
+    }
+
+    #[test]
+    fn text_search_ignores_case() {
+        let item = Todo { content: "Release Notes".to_string(), ..todo("x", Status::Pending, Priority::P2, None) };
+        assert!(Filter::parse("release").unwrap().matches(&item));
+        assert!(Filter::search("rel.*notes", true).unwrap().matches(&item));
+        assert!(Filter::search("[", true).is_err());
+    }
+}
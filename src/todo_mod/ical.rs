@@ -0,0 +1,397 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::todo_mod::import::{finish, ImportError, Parsed};
+use crate::todo_mod::todo_list::{Priority, Status, Todo};
+use crate::todo_mod::view::Entry;
+
+/// 本工具生成的 UID 后缀，导入时据此还原事项 ID
+const UID_SUFFIX: &str = "@small_tools";
+const PRODID: &str = "-//small_tools//todo//ZH";
+
+/// 事项在日历中的 UID：从日历软件导入的沿用原 UID，否则由事项 ID 生成，保证多次导出一致
+pub fn uid(todo: &Todo) -> String {
+    todo.uid.clone().unwrap_or_else(|| format!("{}{}", todo.id, UID_SUFFIX))
+}
+
+/// 导出为 VTODO，`events` 为 true 时为有截止时间的未关闭事项额外生成截止时刻的 VEVENT
+pub fn to_ics(entries: &[Entry], events: bool) -> String {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        format!("PRODID:{}", PRODID),
+    ];
+    let stamp = format_utc(Utc::now().timestamp());
+    for (_, t) in entries {
+        lines.push(String::from("BEGIN:VTODO"));
+        lines.push(format!("UID:{}", escape(&uid(t))));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("CREATED:{}", format_utc(t.create_time)));
+        if let Some(updated) = t.updated_time {
+            lines.push(format!("LAST-MODIFIED:{}", format_utc(updated)));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&t.title)));
+        if !t.content.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(&t.content)));
+        }
+        if let Some(due) = t.dead_line {
+            lines.push(format!("DUE:{}", format_utc(due)));
+        }
+        lines.push(format!("STATUS:{}", status_value(t.status)));
+        if let Some(completed) = t.completed_time.filter(|_| t.status == Status::Done) {
+            lines.push(format!("COMPLETED:{}", format_utc(completed)));
+        }
+        lines.push(format!("PRIORITY:{}", priority_value(t.priority)));
+        if !t.tags.is_empty() {
+            let tags: Vec<String> = t.tags.iter().map(|tag| escape(tag)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        if let Some(project) = &t.project {
+            lines.push(format!("X-SMALL-TOOLS-PROJECT:{}", escape(project)));
+        }
+        for dep in &t.depends_on {
+            lines.push(format!("RELATED-TO;RELTYPE=DEPENDS-ON:{}", escape(&dependency_uid(entries, dep))));
+        }
+        lines.push(String::from("END:VTODO"));
+
+        if events
+            && let Some(due) = t.dead_line.filter(|_| !t.status.is_closed()) {
+            lines.push(String::from("BEGIN:VEVENT"));
+            lines.push(format!("UID:{}", escape(&format!("due-{}", uid(t)))));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!("DTSTART:{}", format_utc(due)));
+            lines.push(format!("DTEND:{}", format_utc(due)));
+            lines.push(format!("SUMMARY:{}", escape(&format!("截止: {}", t.title))));
+            lines.push(format!("RELATED-TO:{}", escape(&uid(t))));
+            lines.push(String::from("END:VEVENT"));
+        }
+    }
+    lines.push(String::from("END:VCALENDAR"));
+    lines.iter().map(|l| fold(l)).collect::<Vec<_>>().concat()
+}
+
+// 依赖的事项一同导出时使用它的 UID（可能是日历软件的原 UID），否则由 ID 生成
+fn dependency_uid(entries: &[Entry], dep: &str) -> String {
+    entries.iter()
+        .find(|(_, t)| t.id.eq_ignore_ascii_case(dep))
+        .map(|(_, t)| uid(t))
+        .unwrap_or_else(|| format!("{}{}", dep, UID_SUFFIX))
+}
+
+fn format_utc(ts: i64) -> String {
+    DateTime::from_timestamp(ts, 0)
+        .map(|t| t.format("%Y%m%dT%H%M%SZ").to_string())
+        .unwrap_or_default()
+}
+
+fn status_value(status: Status) -> &'static str {
+    match status {
+        Status::Pending => "NEEDS-ACTION",
+        Status::InProgress => "IN-PROCESS",
+        Status::Done => "COMPLETED",
+        Status::Cancelled => "CANCELLED",
+    }
+}
+
+// iCalendar 的优先级 1 最高、9 最低、0 表示未指定
+fn priority_value(priority: Priority) -> u8 {
+    match priority {
+        Priority::P0 => 1,
+        Priority::P1 => 3,
+        Priority::P2 => 5,
+        Priority::P3 => 9,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => {},
+        }
+    }
+    out
+}
+
+// 每行不超过 75 字节，续行以空格开头，不拆开多字节字符
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+// 合并续行，返回 (行号, 内容)，行号为逻辑行第一行的位置
+fn unfold(text: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ => lines.push((i + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+/// 一个内容行，如 `DUE;VALUE=DATE:20261103`
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Option<Property> {
+        // 参数值可以带引号，引号内的冒号不是分隔符
+        let mut in_quotes = false;
+        let colon = line.char_indices().find(|&(_, c)| {
+            if c == '"' {
+                in_quotes = !in_quotes;
+            }
+            c == ':' && !in_quotes
+        })?.0;
+        let mut head = line[..colon].split(';');
+        let name = head.next()?.trim().to_uppercase();
+        let params = head
+            .filter_map(|p| p.split_once('='))
+            .map(|(k, v)| (k.trim().to_uppercase(), v.trim_matches('"').to_string()))
+            .collect();
+        Some(Property { name, params, value: line[colon + 1..].to_string() })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// 解析时间值：带 Z 的按 UTC，不带时区的按本地时间；只有日期的截止时间取当天结束，其余取当天开始
+fn parse_time(prop: &Property, end_of_day: bool) -> Option<i64> {
+    let value = prop.value.trim();
+    if prop.param("VALUE") == Some("DATE") || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        let time = if end_of_day { date.and_hms_opt(23, 59, 59)? } else { date.and_hms_opt(0, 0, 0)? };
+        return Local.from_local_datetime(&time).earliest().map(|t| t.timestamp());
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let dt = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&dt).timestamp());
+    }
+    // TZID 指定的时区无法在不依赖时区库的情况下换算，按本地时间处理
+    let dt = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Local.from_local_datetime(&dt).earliest().map(|t| t.timestamp())
+}
+
+fn parse_status(value: &str) -> Option<Status> {
+    match value.trim().to_uppercase().as_str() {
+        "NEEDS-ACTION" => Some(Status::Pending),
+        "IN-PROCESS" => Some(Status::InProgress),
+        "COMPLETED" => Some(Status::Done),
+        "CANCELLED" => Some(Status::Cancelled),
+        _ => None,
+    }
+}
+
+fn parse_priority(value: &str) -> Option<Priority> {
+    match value.trim().parse::<u8>().ok()? {
+        0 => Some(Priority::default()),
+        1 | 2 => Some(Priority::P0),
+        3 | 4 => Some(Priority::P1),
+        5 | 6 => Some(Priority::P2),
+        7..=9 => Some(Priority::P3),
+        _ => None,
+    }
+}
+
+// 本工具生成的 UID 还原为事项 ID，其他 UID 原样保留
+fn apply_uid(todo: &mut Todo, uid: &str) {
+    match uid.strip_suffix(UID_SUFFIX) {
+        Some(id) => todo.id = id.to_lowercase(),
+        None => todo.uid = Some(uid.to_string()),
+    }
+}
+
+/// 只导入 VTODO，VEVENT 等其他组件会被忽略
+pub fn parse_ics(text: &str) -> (Vec<Parsed>, Vec<ImportError>) {
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    // 当前 VTODO 的起始行号、内容和第一处错误
+    let mut current: Option<(usize, Todo, Option<String>)> = None;
+    // 嵌套在 VTODO 中的 VALARM 等子组件
+    let mut nested = 0;
+    for (line, content) in unfold(text) {
+        if content.trim().is_empty() {
+            continue;
+        }
+        let Some(prop) = Property::parse(&content) else {
+            if let Some((_, _, error)) = current.as_mut() {
+                error.get_or_insert_with(|| format!("第 {} 行格式错误: {}", line, content));
+            }
+            continue;
+        };
+        let value = prop.value.trim();
+        match (prop.name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VTODO") => {
+                current = Some((line, Todo::default(), None));
+            },
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VTODO") => {
+                let Some((start, mut todo, error)) = current.take() else {
+                    continue;
+                };
+                let result = match error {
+                    Some(message) => Err(message),
+                    None => finish(&mut todo).map_err(|e| format!("VTODO {}", e)),
+                };
+                match result {
+                    Ok(()) => items.push((start, todo)),
+                    Err(message) => errors.push(ImportError { line: start, message }),
+                }
+            },
+            (_, Some(_)) if nested > 0 => {},
+            (_, Some((_, todo, error))) => {
+                if let Err(message) = apply(todo, &prop) {
+                    error.get_or_insert(format!("第 {} 行: {}", line, message));
+                }
+            },
+            _ => {},
+        }
+    }
+    if let Some((start, _, _)) = current {
+        errors.push(ImportError { line: start, message: String::from("VTODO 缺少 END:VTODO") });
+    }
+    (items, errors)
+}
+
+fn apply(todo: &mut Todo, prop: &Property) -> Result<(), String> {
+    let value = prop.value.trim();
+    let time = |end_of_day| parse_time(prop, end_of_day).ok_or_else(|| format!("无效的时间: {}", value));
+    match prop.name.as_str() {
+        "UID" => apply_uid(todo, &unescape(value)),
+        "SUMMARY" => todo.title = unescape(value),
+        "DESCRIPTION" => todo.content = unescape(value),
+        "DUE" => todo.dead_line = Some(time(true)?),
+        "CREATED" => todo.create_time = time(false)?,
+        "LAST-MODIFIED" => todo.updated_time = Some(time(false)?),
+        "COMPLETED" => todo.completed_time = Some(time(false)?),
+        "STATUS" => todo.status = parse_status(value).ok_or_else(|| format!("无效的状态: {}", value))?,
+        "PRIORITY" => todo.priority = parse_priority(value).ok_or_else(|| format!("无效的优先级: {}", value))?,
+        "CATEGORIES" => {
+            for tag in split_list(value) {
+                if !tag.is_empty() && !todo.has_tag(&tag) {
+                    todo.tags.push(tag);
+                }
+            }
+        },
+        "X-SMALL-TOOLS-PROJECT" => todo.project = Some(unescape(value)).filter(|p| !p.is_empty()),
+        // 本工具生成的 UID 还原为事项 ID，其他 UID 原样保留，由 `Todos::import` 对应到事项
+        "RELATED-TO" if prop.param("RELTYPE").is_some_and(|r| r.eq_ignore_ascii_case("DEPENDS-ON")) => {
+            let related = unescape(value);
+            match related.strip_suffix(UID_SUFFIX) {
+                Some(id) => todo.depends_on.push(id.to_lowercase()),
+                None if !related.is_empty() => todo.depends_on.push(related),
+                None => {},
+            }
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+// 按未转义的逗号拆分列表值
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            _ if escaped => {
+                current.push('\\');
+                current.push(c);
+                escaped = false;
+            },
+            '\\' => escaped = true,
+            ',' => items.push(unescape(std::mem::take(&mut current).trim())),
+            _ => current.push(c),
+        }
+    }
+    items.push(unescape(current.trim()));
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo_mod::todo_list::Todos;
+
+    fn todo(id: &str, uid: Option<&str>, title: &str, deps: &[&str]) -> Todo {
+        Todo {
+            id: id.to_string(),
+            uid: uid.map(str::to_string),
+            title: title.to_string(),
+            create_time: 1760000000,
+            depends_on: deps.iter().map(|d| d.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn dependency_on_calendar_todo_uses_its_uid() {
+        let todos = [
+            todo("ab12", Some("event-1@calendar.example"), "来自日历", &[]),
+            todo("cd34", None, "本地", &["ab12", "zz99"]),
+        ];
+        let entries: Vec<Entry> = todos.iter().enumerate().collect();
+        let text = to_ics(&entries, false);
+        assert!(text.contains("RELATED-TO;RELTYPE=DEPENDS-ON:event-1@calendar.example\r\n"));
+        assert!(text.contains("RELATED-TO;RELTYPE=DEPENDS-ON:zz99@small_tools\r\n"));
+
+        let (items, errors) = parse_ics(&text);
+        assert!(errors.is_empty());
+        assert_eq!(items[1].1.depends_on, ["event-1@calendar.example", "zz99"]);
+
+        // 导入到新的列表时，日历 UID 对应到为该事项分配的 ID
+        let mut imported = Todos::default();
+        imported.import(items);
+        let calendar_id = imported.todos[0].id.clone();
+        assert!(!calendar_id.is_empty());
+        assert_eq!(imported.todos[1].depends_on, [calendar_id]);
+    }
+
+    #[test]
+    fn folded_lines_round_trip() {
+        let long = "很长的标题，".repeat(20);
+        let todos = [todo("ab12", None, &long, &[])];
+        let entries: Vec<Entry> = todos.iter().enumerate().collect();
+        let text = to_ics(&entries, true);
+        assert!(text.lines().all(|l| l.len() <= 75));
+        let (items, errors) = parse_ics(&text);
+        assert!(errors.is_empty());
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].1.title, long);
+        assert_eq!(items[0].1.id, "ab12");
+    }
+}
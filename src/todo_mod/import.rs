@@ -0,0 +1,730 @@
+use std::collections::HashMap;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+
+use crate::storage_mod::schema::Schema;
+use crate::todo_mod::deadline::parse_deadline;
+use crate::todo_mod::depend;
+use crate::todo_mod::ical;
+use crate::todo_mod::subtask::Subtask;
+use crate::todo_mod::todo_list::{parse_project, parse_tags, Priority, Status, Todo, Todos, SCHEMA};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    TodoTxt,
+    Json,
+    Ics,
+}
+
+impl ImportFormat {
+    pub fn parse(input: &str) -> Option<ImportFormat> {
+        match input.trim().to_lowercase().as_str() {
+            "csv" => Some(ImportFormat::Csv),
+            "txt" | "todotxt" | "todo.txt" => Some(ImportFormat::TodoTxt),
+            "json" => Some(ImportFormat::Json),
+            "ics" | "ical" | "icalendar" => Some(ImportFormat::Ics),
+            _ => None,
+        }
+    }
+
+    /// 根据文件扩展名推断格式
+    pub fn from_path(path: &str) -> Option<ImportFormat> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?;
+        ImportFormat::parse(ext)
+    }
+}
+
+/// CSV 列可以映射到的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Id,
+    Title,
+    Content,
+    Status,
+    Priority,
+    Project,
+    Tags,
+    Due,
+    Created,
+    Completed,
+    DependsOn,
+    Subtasks,
+}
+
+impl Field {
+    pub fn parse(input: &str) -> Option<Field> {
+        match input.trim().to_lowercase().as_str() {
+            "id" => Some(Field::Id),
+            "title" | "name" | "summary" | "task" | "标题" => Some(Field::Title),
+            "content" | "description" | "notes" | "note" | "内容" => Some(Field::Content),
+            "status" | "state" | "状态" => Some(Field::Status),
+            "priority" | "pri" | "优先级" => Some(Field::Priority),
+            "project" | "项目" => Some(Field::Project),
+            "tags" | "tag" | "labels" | "标签" => Some(Field::Tags),
+            "due" | "deadline" | "dead_line" | "due date" | "截止时间" => Some(Field::Due),
+            "created" | "create_time" | "created at" | "创建时间" => Some(Field::Created),
+            "completed" | "completed_time" | "completed at" | "完成时间" => Some(Field::Completed),
+            "depends_on" | "depends" | "dependencies" | "依赖" => Some(Field::DependsOn),
+            "subtasks" | "checklist" | "检查项" => Some(Field::Subtasks),
+            _ => None,
+        }
+    }
+}
+
+/// 解析 `列名=字段,列名=字段` 形式的表头映射，列名不区分大小写
+pub fn parse_mapping(input: &str) -> Result<HashMap<String, Field>, String> {
+    let mut mapping = HashMap::new();
+    for pair in input.split([',', '，']).filter(|p| !p.trim().is_empty()) {
+        let Some((column, field)) = pair.split_once('=') else {
+            return Err(format!("无效的列映射 '{}'，应为 列名=字段", pair.trim()));
+        };
+        let field = Field::parse(field).ok_or_else(|| format!("未知的字段: {}", field.trim()))?;
+        mapping.insert(column.trim().to_lowercase(), field);
+    }
+    Ok(mapping)
+}
+
+/// 带行号的导入错误
+#[derive(Debug, Clone)]
+pub struct ImportError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// 解析得到的事项及其所在行号
+pub type Parsed = (usize, Todo);
+
+/// 解析文件内容，返回成功解析的事项和逐行的错误
+pub fn parse(format: ImportFormat, text: &str, mapping: &HashMap<String, Field>) -> (Vec<Parsed>, Vec<ImportError>) {
+    match format {
+        ImportFormat::Csv => parse_csv(text, mapping),
+        ImportFormat::TodoTxt => parse_todo_txt(text),
+        ImportFormat::Json => parse_json(text),
+        ImportFormat::Ics => ical::parse_ics(text),
+    }
+}
+
+fn parse_csv(text: &str, mapping: &HashMap<String, Field>) -> (Vec<Parsed>, Vec<ImportError>) {
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(text.as_bytes());
+    let columns: Vec<Option<Field>> = match reader.headers() {
+        Ok(headers) => headers.iter()
+            .map(|h| {
+                let h = h.trim().trim_start_matches('\u{feff}').to_lowercase();
+                mapping.get(&h).copied().or_else(|| Field::parse(&h))
+            })
+            .collect(),
+        Err(e) => {
+            errors.push(ImportError { line: 1, message: format!("无法读取表头: {}", e) });
+            return (items, errors);
+        }
+    };
+    if !columns.contains(&Some(Field::Title)) {
+        errors.push(ImportError {
+            line: 1,
+            message: String::from("表头中没有标题列，请用 --map \"列名=title\" 指定"),
+        });
+        return (items, errors);
+    }
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map(|p| p.line() as usize).unwrap_or_default();
+                errors.push(ImportError { line, message: e.to_string() });
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line() as usize).unwrap_or_default();
+        if record.iter().all(|v| v.trim().is_empty()) {
+            continue;
+        }
+        let mut todo = Todo::default();
+        let mut result = Ok(());
+        for (field, value) in columns.iter().zip(record.iter()) {
+            if let Some(field) = field
+                && !value.trim().is_empty() {
+                result = result.and_then(|_| apply_field(&mut todo, *field, value.trim()));
+            }
+        }
+        match result.and_then(|_| finish(&mut todo)) {
+            Ok(()) => items.push((line, todo)),
+            Err(message) => errors.push(ImportError { line, message }),
+        }
+    }
+    (items, errors)
+}
+
+fn apply_field(todo: &mut Todo, field: Field, value: &str) -> Result<(), String> {
+    match field {
+        Field::Id => todo.id = value.to_lowercase(),
+        Field::Title => todo.title = value.to_string(),
+        Field::Content => todo.content = value.to_string(),
+        Field::Status => {
+            let status = match value.to_lowercase().as_str() {
+                "x" | "completed" | "true" | "yes" => Some(Status::Done),
+                "false" | "no" | "open" => Some(Status::Pending),
+                v => Status::parse(v),
+            };
+            todo.status = status.ok_or_else(|| format!("无效的状态: {}", value))?;
+        },
+        Field::Priority => todo.priority = parse_priority(value)
+            .ok_or_else(|| format!("无效的优先级: {}", value))?,
+        Field::Project => todo.project = parse_project(value),
+        Field::Tags => todo.tags = parse_tags(value),
+        Field::Due => todo.dead_line = parse_deadline(value, Local::now())?,
+        Field::Created => todo.create_time = parse_time(value)
+            .ok_or_else(|| format!("无效的创建时间: {}", value))?,
+        Field::Completed => todo.completed_time = Some(parse_time(value)
+            .ok_or_else(|| format!("无效的完成时间: {}", value))?),
+        Field::DependsOn => todo.depends_on = value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|id| !id.is_empty())
+            .map(str::to_lowercase)
+            .collect(),
+        Field::Subtasks => todo.subtasks = value.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(parse_subtask)
+            .collect(),
+    }
+    Ok(())
+}
+
+// 检查项以 [x] / [ ] 开头表示是否已勾选
+fn parse_subtask(line: &str) -> Subtask {
+    let line = line.trim().trim_start_matches("- ");
+    for (prefix, done) in [("[x]", true), ("[X]", true), ("[ ]", false)] {
+        if let Some(text) = line.strip_prefix(prefix) {
+            return Subtask { text: text.trim().to_string(), done };
+        }
+    }
+    Subtask { text: line.to_string(), done: false }
+}
+
+/// 支持 P0-P3 以及 todo.txt 的 A-D，字母靠后的都视为 P3
+fn parse_priority(value: &str) -> Option<Priority> {
+    if let Some(priority) = Priority::parse(value) {
+        return Some(priority);
+    }
+    let value = value.trim().trim_start_matches('(').trim_end_matches(')');
+    match value.to_ascii_uppercase().as_str() {
+        "A" | "HIGH" => Some(Priority::P0),
+        "B" => Some(Priority::P1),
+        "C" | "MEDIUM" => Some(Priority::P2),
+        "LOW" => Some(Priority::P3),
+        v if v.len() == 1 && v.chars().all(|c| c.is_ascii_uppercase()) => Some(Priority::P3),
+        _ => None,
+    }
+}
+
+/// 解析创建/完成时间：日期时间、只有日期（当天零点）、RFC 3339 或 Unix 时间戳
+fn parse_time(value: &str) -> Option<i64> {
+    let value = value.trim().replace('/', "-");
+    if let Ok(ts) = value.parse::<i64>() {
+        return Local.timestamp_opt(ts, 0).single().map(|_| ts);
+    }
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(&value) {
+        return Some(dt.timestamp());
+    }
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(&value, fmt) {
+            return Local.from_local_datetime(&dt).earliest().map(|t| t.timestamp());
+        }
+    }
+    let date = NaiveDate::parse_from_str(&value, "%Y-%m-%d").ok()?;
+    Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest().map(|t| t.timestamp())
+}
+
+/// 校验并补齐缺省值
+pub fn finish(todo: &mut Todo) -> Result<(), String> {
+    todo.title = todo.title.trim().to_string();
+    if todo.title.is_empty() {
+        return Err(String::from("标题不能为空"));
+    }
+    if todo.create_time == 0 {
+        todo.create_time = Local::now().timestamp();
+    }
+    // JSON 等格式中的时间戳未经解析，超出可表示范围时显示和排序都会出错
+    for (label, ts) in [
+        ("创建时间", Some(todo.create_time)),
+        ("截止时间", todo.dead_line),
+        ("完成时间", todo.completed_time),
+        ("更新时间", todo.updated_time),
+    ] {
+        if let Some(ts) = ts
+            && Local.timestamp_opt(ts, 0).single().is_none() {
+            return Err(format!("无效的{}: {}", label, ts));
+        }
+    }
+    if todo.status.is_closed() {
+        if todo.completed_time.is_none() {
+            todo.completed_time = Some(Local::now().timestamp());
+        }
+    } else {
+        todo.completed_time = None;
+    }
+    Ok(())
+}
+
+/// 每行一条，格式同导出：`x 完成日期 创建日期 (优先级) 标题 +项目 @标签 键:值`
+fn parse_todo_txt(text: &str) -> (Vec<Parsed>, Vec<ImportError>) {
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut todo = match parse_todo_txt_line(line.trim()) {
+            Ok(todo) => todo,
+            Err(message) => {
+                errors.push(ImportError { line: i + 1, message });
+                continue;
+            }
+        };
+        match finish(&mut todo) {
+            Ok(()) => items.push((i + 1, todo)),
+            Err(message) => errors.push(ImportError { line: i + 1, message }),
+        }
+    }
+    (items, errors)
+}
+
+fn parse_todo_txt_line(line: &str) -> Result<Todo, String> {
+    let mut todo = Todo::default();
+    let mut words = line.split_whitespace().peekable();
+    let is_date = |w: &str| NaiveDate::parse_from_str(w, "%Y-%m-%d").is_ok();
+    if words.peek() == Some(&"x") {
+        words.next();
+        todo.status = Status::Done;
+        if let Some(date) = words.next_if(|w| is_date(w)) {
+            todo.completed_time = parse_time(date);
+        }
+    } else if let Some(priority) = words.next_if(|w| w.len() == 3 && w.starts_with('(') && w.ends_with(')')) {
+        todo.priority = parse_priority(priority).ok_or_else(|| format!("无效的优先级: {}", priority))?;
+    }
+    if let Some(date) = words.next_if(|w| is_date(w)) {
+        todo.create_time = parse_time(date).unwrap_or_default();
+    }
+    let mut title = Vec::new();
+    for word in words {
+        if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+            // 只保留第一个项目，其余作为标签
+            if todo.project.is_none() {
+                todo.project = Some(project.to_string());
+            } else {
+                todo.tags.push(project.to_string());
+            }
+            continue;
+        }
+        if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+            todo.tags.push(context.to_string());
+            continue;
+        }
+        match word.split_once(':') {
+            Some(("due", value)) => todo.dead_line = parse_deadline(value, Local::now())?,
+            Some(("id", value)) => todo.id = value.to_lowercase(),
+            Some(("dep", value)) => todo.depends_on = value.split(',')
+                .filter(|id| !id.is_empty())
+                .map(str::to_lowercase)
+                .collect(),
+            Some(("pri", value)) => todo.priority = parse_priority(value)
+                .ok_or_else(|| format!("无效的优先级: {}", value))?,
+            Some(("status", value)) => todo.status = Status::parse(value)
+                .ok_or_else(|| format!("无效的状态: {}", value))?,
+            _ => title.push(word),
+        }
+    }
+    todo.title = title.join(" ");
+    Ok(todo)
+}
+
+// 本工具的 data.json 格式：带版本号的外层结构，或旧版本不带外层结构的 Todo 数组
+fn parse_json(text: &str) -> (Vec<Parsed>, Vec<ImportError>) {
+    let value: serde_json::Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(e) => {
+            let message = format!("JSON 格式错误（第 {} 列）: {}", e.column(), e);
+            return (Vec::new(), vec![ImportError { line: e.line(), message }]);
+        }
+    };
+    let enveloped = value.is_object();
+    let values = match Schema::unwrap(value)
+        .and_then(|(version, data)| SCHEMA.upgrade(version, data))
+        .and_then(|data| serde_json::from_value::<Vec<serde_json::Value>>(data).map_err(|e| e.to_string())) {
+        Ok(values) => values,
+        Err(message) => return (Vec::new(), vec![ImportError { line: 1, message }]),
+    };
+    // 外层结构中的数组位于第二层
+    let lines = element_lines(text, if enveloped { 2 } else { 1 });
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    for (i, value) in values.into_iter().enumerate() {
+        let line = lines.get(i).copied().unwrap_or_default();
+        let result = serde_json::from_value::<Todo>(value)
+            .map_err(|e| e.to_string())
+            .and_then(|mut todo| finish(&mut todo).map(|_| todo));
+        match result {
+            Ok(todo) => items.push((line, todo)),
+            Err(message) => errors.push(ImportError { line, message }),
+        }
+    }
+    (items, errors)
+}
+
+// 返回位于第 level 层的数组中每个元素起始处的行号，用于定位出错的元素
+fn element_lines(text: &str, level: usize) -> Vec<usize> {
+    let mut lines = Vec::new();
+    let (mut line, mut depth) = (1, 0);
+    let (mut in_string, mut escaped, mut expect_value) = (false, false, false);
+    for c in text.chars() {
+        if c == '\n' {
+            line += 1;
+        }
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {},
+            }
+            continue;
+        }
+        if expect_value && !c.is_whitespace() && c != ']' {
+            lines.push(line);
+            expect_value = false;
+        }
+        match c {
+            '"' => in_string = true,
+            '[' | '{' => {
+                depth += 1;
+                if depth == level && c == '[' {
+                    expect_value = true;
+                }
+            },
+            ']' | '}' => depth -= 1,
+            ',' if depth == level => expect_value = true,
+            _ => {},
+        }
+    }
+    lines
+}
+
+/// 导入结果
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub added: usize,
+    /// 与已有事项或同批事项重复而跳过的行号和标题
+    pub duplicates: Vec<(usize, String)>,
+    pub warnings: Vec<String>,
+}
+
+// 按 ID、日历 UID 或“标题 + 项目”判断是否重复
+fn same_item(a: &Todo, b: &Todo) -> bool {
+    if !a.id.is_empty() && a.id.eq_ignore_ascii_case(&b.id) {
+        return true;
+    }
+    if a.uid.is_some() && a.uid == b.uid {
+        return true;
+    }
+    let normalize = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    normalize(&a.title) == normalize(&b.title)
+        && a.project.as_deref().map(normalize) == b.project.as_deref().map(normalize)
+}
+
+impl Todos {
+    /// 追加导入的事项：跳过重复项，保留不冲突的原 ID 以维持依赖关系，
+    /// 去掉指向不存在事项的依赖；只修改内存，由调用方统一保存一次
+    pub fn import(&mut self, items: Vec<Parsed>) -> ImportReport {
+        let mut report = ImportReport::default();
+        let start = self.todos.len();
+        for (line, mut todo) in items {
+            if self.todos.iter().any(|t| same_item(&todo, t)) {
+                report.duplicates.push((line, todo.title));
+                continue;
+            }
+            let id = std::mem::take(&mut todo.id);
+            let valid_id = id.len() >= 2 && id.chars().all(|c| c.is_ascii_alphanumeric())
+                && id.starts_with(|c: char| c.is_ascii_alphabetic());
+            if valid_id {
+                todo.id = id;
+                self.todos.push(todo);
+            } else {
+                self.add_todo(todo);
+            }
+            report.added += 1;
+        }
+        for i in start..self.todos.len() {
+            // 从日历导入的依赖是对方的 UID，换成对应事项的 ID
+            let (known, unknown): (Vec<String>, Vec<String>) = std::mem::take(&mut self.todos[i].depends_on)
+                .into_iter()
+                .map(|dep| self.todos.iter()
+                    .find(|t| t.uid.as_deref() == Some(dep.as_str()))
+                    .map(|t| t.id.clone())
+                    .unwrap_or(dep))
+                .partition(|id| self.todos.iter().any(|t| t.id.eq_ignore_ascii_case(id)));
+            if !unknown.is_empty() {
+                report.warnings.push(format!(
+                    "'{}' 依赖的事项不存在，已忽略: {}", self.todos[i].title, unknown.join(", ")
+                ));
+            }
+            // 逐条加回依赖，会形成循环的依赖不予保留
+            for id in known {
+                if depend::reaches(&self.todos, &id, &self.todos[i].id) {
+                    report.warnings.push(format!(
+                        "'{}' 依赖 {} 会形成循环依赖，已忽略", self.todos[i].title, id
+                    ));
+                } else {
+                    self.todos[i].depends_on.push(id);
+                }
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo_mod::export::ExportFormat;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32, sec: u32) -> i64 {
+        Local.with_ymd_and_hms(y, m, d, h, min, sec).unwrap().timestamp()
+    }
+
+    fn todos(items: &[(&str, &str, &[&str])]) -> Todos {
+        let mut todos = Todos::default();
+        for (id, title, deps) in items {
+            todos.todos.push(Todo {
+                id: id.to_string(),
+                title: title.to_string(),
+                depends_on: deps.iter().map(|d| d.to_string()).collect(),
+                ..Default::default()
+            });
+        }
+        todos
+    }
+
+    fn parsed(items: &[(&str, &str, &[&str])]) -> Vec<Parsed> {
+        todos(items).todos.into_iter().enumerate().map(|(i, t)| (i + 2, t)).collect()
+    }
+
+    #[test]
+    fn element_lines_of_plain_and_enveloped_arrays() {
+        let plain = "[\n  {\"title\": \"a\"},\n  {\"title\": \"[b,]\"}, {\"title\": \"c\\\"\"}\n]";
+        assert_eq!(element_lines(plain, 1), [2, 3, 3]);
+        let enveloped = "{\n  \"version\": 1,\n  \"data\": [\n    {\"tags\": [\"x\", \"y\"]},\n\n    {}\n  ]\n}";
+        assert_eq!(element_lines(enveloped, 2), [4, 6]);
+        assert_eq!(element_lines("[]", 1), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn json_errors_point_to_element_line() {
+        let text = "{\"version\": 1, \"data\": [\n{\"title\": \"a\", \"content\": \"\", \"create_time\": 1},\n{\"title\": \"b\"}\n]}";
+        let (items, errors) = parse_json(text);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].0, 2);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 3);
+    }
+
+    #[test]
+    fn todo_txt_line() {
+        let todo = parse_todo_txt_line("(A) 2026-10-01 写周报 +work @office @home due:2026-10-20 id:ab12 dep:cd34,ef56").unwrap();
+        assert_eq!(todo.priority, Priority::P0);
+        assert_eq!(todo.create_time, at(2026, 10, 1, 0, 0, 0));
+        assert_eq!(todo.title, "写周报");
+        assert_eq!(todo.project.as_deref(), Some("work"));
+        assert_eq!(todo.tags, ["office", "home"]);
+        assert_eq!(todo.dead_line, Some(at(2026, 10, 20, 23, 59, 59)));
+        assert_eq!(todo.id, "ab12");
+        assert_eq!(todo.depends_on, ["cd34", "ef56"]);
+
+        let todo = parse_todo_txt_line("x 2026-10-05 2026-10-01 发布 +a +b pri:B status:cancelled").unwrap();
+        assert_eq!(todo.status, Status::Cancelled);
+        assert_eq!(todo.completed_time, Some(at(2026, 10, 5, 0, 0, 0)));
+        assert_eq!(todo.create_time, at(2026, 10, 1, 0, 0, 0));
+        assert_eq!(todo.priority, Priority::P1);
+        assert_eq!(todo.project.as_deref(), Some("a"));
+        assert_eq!(todo.tags, ["b"]);
+
+        // 只有一个日期时是完成日期
+        let todo = parse_todo_txt_line("x 2026-10-05 发布").unwrap();
+        assert_eq!(todo.completed_time, Some(at(2026, 10, 5, 0, 0, 0)));
+        assert_eq!(todo.create_time, 0);
+
+        assert!(parse_todo_txt_line("a status:later").is_err());
+        assert!(parse_todo_txt_line("a due:someday").is_err());
+    }
+
+    #[test]
+    fn csv_header_mapping() {
+        let text = "\u{feff}Task Name,Notes,STATUS,Extra\n写周报,整理进展,x,ignored\n,,,\n";
+        let (items, errors) = parse_csv(text, &HashMap::new());
+        assert!(items.is_empty());
+        assert_eq!(errors[0].line, 1);
+
+        let mapping = parse_mapping("task name=title, Notes=content").unwrap();
+        let (items, errors) = parse_csv(text, &mapping);
+        assert!(errors.is_empty());
+        assert_eq!(items.len(), 1);
+        let (line, todo) = &items[0];
+        assert_eq!(*line, 2);
+        assert_eq!((todo.title.as_str(), todo.content.as_str()), ("写周报", "整理进展"));
+        assert_eq!(todo.status, Status::Done);
+        assert!(todo.completed_time.is_some());
+
+        assert!(parse_mapping("name").is_err());
+        assert!(parse_mapping("name=unknown").is_err());
+    }
+
+    #[test]
+    fn csv_invalid_rows() {
+        let text = "title,status,priority,created\na,later,,\nb,,P9,\nc,,,99999999999999999\nd,,,2026-10-01\n";
+        let (items, errors) = parse_csv(text, &HashMap::new());
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].1.create_time, at(2026, 10, 1, 0, 0, 0));
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, [2, 3, 4]);
+    }
+
+    #[test]
+    fn out_of_range_timestamps_are_invalid() {
+        assert_eq!(parse_time("99999999999999999"), None);
+        assert_eq!(parse_time("1760000000"), Some(1760000000));
+        for field in ["create_time", "dead_line", "completed_time", "updated_time"] {
+            let text = format!("[{{\"title\": \"a\", \"content\": \"\", \"create_time\": 1, \"{}\": 99999999999999999}}]", field);
+            let (items, errors) = parse_json(&text);
+            assert!(items.is_empty(), "{}", field);
+            assert_eq!(errors.len(), 1, "{}", field);
+        }
+    }
+
+    #[test]
+    fn import_keeps_valid_ids_and_skips_duplicates() {
+        let mut todos = todos(&[("ab12", "已有", &[])]);
+        let report = todos.import(parsed(&[
+            ("cd34", "新的", &[]),
+            ("ab12", "ID 重复", &[]),
+            ("", "已有", &[]),
+            ("1x", "无效 ID", &[]),
+        ]));
+        assert_eq!(report.added, 2);
+        assert_eq!(report.duplicates, [(3, String::from("ID 重复")), (4, String::from("已有"))]);
+        assert_eq!(todos.todos[1].id, "cd34");
+        assert_ne!(todos.todos[2].id, "1x");
+        assert_eq!(todos.todos[2].id.len(), 6);
+    }
+
+    #[test]
+    fn import_prunes_dangling_and_cyclic_dependencies() {
+        let mut todos = todos(&[("ab12", "已有", &["cd34"]), ("cd34", "已有2", &[])]);
+        let report = todos.import(parsed(&[
+            ("ef56", "a", &["ab12", "zz99"]),
+            ("gh78", "b", &["ef56"]),
+            ("jk90", "c", &["gh78"]),
+        ]));
+        assert_eq!(report.added, 3);
+        assert_eq!(todos.todos[2].depends_on, ["ab12"]);
+        assert_eq!(todos.todos[3].depends_on, ["ef56"]);
+        assert_eq!(todos.todos[4].depends_on, ["gh78"]);
+        assert_eq!(report.warnings.len(), 1);
+
+        let mut todos = Todos::default();
+        // 互相依赖时，先处理的一条会形成循环而被忽略
+        let report = todos.import(parsed(&[("ab12", "a", &["cd34"]), ("cd34", "b", &["ab12"])]));
+        assert!(todos.todos[0].depends_on.is_empty());
+        assert_eq!(todos.todos[1].depends_on, ["ab12"]);
+        assert_eq!(report.warnings.len(), 1);
+    }
+
+    fn sample() -> Vec<Todo> {
+        vec![
+            Todo {
+                id: String::from("ab12"),
+                title: String::from("写周报, \"第 3 周\""),
+                content: String::from("整理进展\n第二行"),
+                create_time: at(2026, 10, 1, 9, 0, 0),
+                dead_line: Some(at(2026, 10, 20, 18, 0, 0)),
+                priority: Priority::P1,
+                tags: vec![String::from("work"), String::from("weekly")],
+                project: Some(String::from("release")),
+                depends_on: vec![String::from("cd34")],
+                subtasks: vec![
+                    Subtask { text: String::from("汇总"), done: true },
+                    Subtask { text: String::from("发送"), done: false },
+                ],
+                ..Default::default()
+            },
+            Todo {
+                id: String::from("cd34"),
+                title: String::from("已完成"),
+                create_time: at(2026, 9, 1, 8, 0, 0),
+                status: Status::Done,
+                completed_time: Some(at(2026, 9, 3, 12, 0, 0)),
+                priority: Priority::P3,
+                ..Default::default()
+            },
+            Todo {
+                id: String::from("ef56"),
+                title: String::from("缺少完成时间"),
+                create_time: at(2026, 9, 2, 8, 0, 0),
+                status: Status::Cancelled,
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn round_trip(format: ExportFormat, import: ImportFormat) -> Vec<Todo> {
+        let todos = sample();
+        let entries: Vec<_> = todos.iter().enumerate().collect();
+        let text = format.render(&entries).unwrap();
+        let (items, errors) = parse(import, &text, &HashMap::new());
+        assert!(errors.is_empty(), "{:?}", errors);
+        items.into_iter().map(|(_, t)| t).collect()
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let original = sample();
+        let imported = round_trip(ExportFormat::Csv, ImportFormat::Csv);
+        assert_eq!(imported.len(), original.len());
+        for (a, b) in original.iter().zip(&imported[..2]) {
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.title, b.title);
+            assert_eq!(a.content, b.content);
+            assert_eq!(a.status, b.status);
+            assert_eq!(a.priority, b.priority);
+            assert_eq!(a.project, b.project);
+            assert_eq!(a.tags, b.tags);
+            assert_eq!(a.create_time, b.create_time);
+            assert_eq!(a.dead_line, b.dead_line);
+            assert_eq!(a.completed_time, b.completed_time);
+            assert_eq!(a.depends_on, b.depends_on);
+            assert_eq!(a.subtasks, b.subtasks);
+        }
+        assert_eq!(imported[2].status, Status::Cancelled);
+        assert_eq!(imported[2].create_time, original[2].create_time);
+    }
+
+    #[test]
+    fn todo_txt_round_trip() {
+        let original = sample();
+        let imported = round_trip(ExportFormat::TodoTxt, ImportFormat::TodoTxt);
+        let day = |ts: i64| Local.timestamp_opt(ts, 0).unwrap().date_naive();
+        assert_eq!(imported[0].title, "写周报, \"第 3 周\"");
+        assert_eq!(imported[0].priority, Priority::P1);
+        assert_eq!(imported[0].project.as_deref(), Some("release"));
+        assert_eq!(imported[0].tags, original[0].tags);
+        assert_eq!(imported[0].depends_on, original[0].depends_on);
+        assert_eq!(day(imported[0].create_time), day(original[0].create_time));
+        assert_eq!(imported[1].status, Status::Done);
+        assert_eq!(imported[1].priority, Priority::P3);
+        assert_eq!(day(imported[1].completed_time.unwrap()), day(original[1].completed_time.unwrap()));
+        assert_eq!(day(imported[1].create_time), day(original[1].create_time));
+        // 没有完成时间时不能把创建日期当作完成日期
+        assert_eq!(imported[2].status, Status::Cancelled);
+        assert_ne!(imported[2].completed_time.map(day), Some(day(original[2].create_time)));
+    }
+}
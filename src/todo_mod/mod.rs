@@ -1 +1,11 @@
-pub mod todo_list;
\ No newline at end of file
+pub mod todo_list;
+pub mod todo_cmd;
+pub mod view;
+pub mod deadline;
+pub mod filter;
+pub mod recurrence;
+pub mod subtask;
+pub mod depend;
+pub mod export;
+pub mod import;
+pub mod ical;
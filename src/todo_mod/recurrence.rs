@@ -0,0 +1,271 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Deserializer, Serialize};
+
+pub const RECURRENCE_HELP: &str = "\
+重复规则:
+  daily / 每天                   每天
+  weekly:mon,thu / 每周一,四     每周的指定几天，只写 weekly 表示每 7 天
+  monthly:15 / 每月15号          每月的第 N 天，超过当月天数时取月末
+  every:3 / 每3天                每隔 N 天
+  none / 无                      不重复";
+
+/// `every:N` 允许的最大间隔，约一百年
+const MAX_EVERY_DAYS: u32 = 36500;
+
+/// 重复规则，完成当前实例后据此生成下一次的截止时间
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Recurrence {
+    Daily,
+    /// 1 表示周一，7 表示周日，为空时每 7 天重复一次
+    Weekly {
+        #[serde(deserialize_with = "weekdays")]
+        weekdays: Vec<u32>,
+    },
+    Monthly { day: u32 },
+    Every { days: u32 },
+}
+
+impl Recurrence {
+    /// 解析重复规则，`Ok(None)` 表示不重复
+    pub fn parse(input: &str) -> Result<Option<Recurrence>, String> {
+        let input = input.trim().to_lowercase();
+        let invalid = || format!("无法识别的重复规则: {}", input);
+        if matches!(input.as_str(), "none" | "no" | "-" | "无" | "不重复") {
+            return Ok(None);
+        }
+        if matches!(input.as_str(), "daily" | "每天" | "每日") {
+            return Ok(Some(Recurrence::Daily));
+        }
+        if matches!(input.as_str(), "weekly" | "每周") {
+            return Ok(Some(Recurrence::Weekly { weekdays: Vec::new() }));
+        }
+        if let Some(days) = input.strip_prefix("weekly:").or_else(|| input.strip_prefix("每周")) {
+            let mut weekdays = Vec::new();
+            for day in days.split([',', '，', '、']) {
+                let day = parse_weekday_number(day.trim()).ok_or_else(invalid)?;
+                if !weekdays.contains(&day) {
+                    weekdays.push(day);
+                }
+            }
+            weekdays.sort_unstable();
+            return Ok(Some(Recurrence::Weekly { weekdays }));
+        }
+        if let Some(day) = input.strip_prefix("monthly:").or_else(|| input.strip_prefix("每月")) {
+            let day: u32 = day.trim_end_matches(['号', '日']).trim().parse().map_err(|_| invalid())?;
+            if !(1..=31).contains(&day) {
+                return Err(format!("每月的日期必须在 1-31 之间: {}", day));
+            }
+            return Ok(Some(Recurrence::Monthly { day }));
+        }
+        if let Some(days) = input.strip_prefix("every:")
+            .or_else(|| input.strip_prefix('每').and_then(|d| d.strip_suffix('天'))) {
+            let days: u32 = days.trim().trim_end_matches('d').parse().map_err(|_| invalid())?;
+            if days == 0 {
+                return Err(String::from("间隔天数必须大于 0"));
+            }
+            if days > MAX_EVERY_DAYS {
+                return Err(format!("间隔天数不能超过 {}: {}", MAX_EVERY_DAYS, days));
+            }
+            return Ok(Some(Recurrence::Every { days }));
+        }
+        Err(invalid())
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Recurrence::Daily => String::from("每天"),
+            Recurrence::Weekly { weekdays } => {
+                const NAMES: [&str; 7] = ["一", "二", "三", "四", "五", "六", "日"];
+                let names: Vec<&str> = weekdays.iter()
+                    .filter_map(|d| NAMES.get(d.checked_sub(1)? as usize).copied())
+                    .collect();
+                if names.is_empty() {
+                    String::from("每周")
+                } else {
+                    format!("每周{}", names.join("、"))
+                }
+            },
+            Recurrence::Monthly { day } => format!("每月 {} 日", day),
+            Recurrence::Every { days } => format!("每 {} 天", days),
+        }
+    }
+
+    /// 以 `base`（当前实例的截止时间）为起点计算下一次截止时间，保留原来的时分；
+    /// 结果早于 `now` 时继续顺延，避免补建一连串已经过期的实例；日期超出可表示的范围时返回 `None`
+    pub fn next_after(&self, base: i64, now: i64) -> Option<i64> {
+        let base = Local.timestamp_opt(base, 0).single()?;
+        let time = base.time();
+        let mut date = self.step(base.date_naive())?;
+        loop {
+            let next = to_local(date.and_time(time))?;
+            if next > now {
+                return Some(next);
+            }
+            date = self.step(date)?;
+        }
+    }
+
+    fn step(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let add_days = |n: i64| date.checked_add_signed(Duration::days(n));
+        let next_date = match self {
+            Recurrence::Daily => add_days(1)?,
+            Recurrence::Every { days } => add_days(*days as i64)?,
+            Recurrence::Weekly { weekdays } => {
+                if weekdays.is_empty() {
+                    add_days(7)?
+                } else {
+                    (1..=7).map_while(add_days)
+                        .find(|d| weekdays.contains(&d.weekday().number_from_monday()))?
+                }
+            },
+            Recurrence::Monthly { day } => {
+                let this_month = clamp_day(date.year(), date.month(), *day)?;
+                if this_month > date {
+                    this_month
+                } else {
+                    let (year, month) = if date.month() == 12 {
+                        (date.year() + 1, 1)
+                    } else {
+                        (date.year(), date.month() + 1)
+                    };
+                    clamp_day(year, month, *day)?
+                }
+            },
+        };
+        Some(next_date)
+    }
+}
+
+// 有歧义时取较早的时刻；落在夏令时跳过的时段时顺延到切换之后
+fn to_local(dt: NaiveDateTime) -> Option<i64> {
+    (0..=4).map_while(|i| dt.checked_add_signed(Duration::minutes(30 * i)))
+        .find_map(|dt| Local.from_local_datetime(&dt).earliest())
+        .map(|t| t.timestamp())
+}
+
+// 手工编辑数据文件可能写入 1-7 以外的值，读取时丢弃并去重
+fn weekdays<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u32>, D::Error> {
+    let mut days: Vec<u32> = Vec::deserialize(deserializer)?;
+    days.retain(|d| (1..=7).contains(d));
+    days.sort_unstable();
+    days.dedup();
+    Ok(days)
+}
+
+// 日期超出当月天数时取当月最后一天
+fn clamp_day(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    (1..=day).rev().find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+}
+
+fn parse_weekday_number(input: &str) -> Option<u32> {
+    let input = input.trim_start_matches("星期").trim_start_matches('周');
+    match input {
+        "mon" | "monday" | "一" | "1" => Some(1),
+        "tue" | "tuesday" | "二" | "2" => Some(2),
+        "wed" | "wednesday" | "三" | "3" => Some(3),
+        "thu" | "thursday" | "四" | "4" => Some(4),
+        "fri" | "friday" | "五" | "5" => Some(5),
+        "sat" | "saturday" | "六" | "6" => Some(6),
+        "sun" | "sunday" | "日" | "天" | "7" => Some(7),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> i64 {
+        Local.with_ymd_and_hms(y, m, d, h, min, 0).unwrap().timestamp()
+    }
+
+    #[test]
+    fn parse_rules() {
+        assert_eq!(Recurrence::parse("daily"), Ok(Some(Recurrence::Daily)));
+        assert_eq!(Recurrence::parse("每周四,一,四"), Ok(Some(Recurrence::Weekly { weekdays: vec![1, 4] })));
+        assert_eq!(Recurrence::parse("weekly:sun"), Ok(Some(Recurrence::Weekly { weekdays: vec![7] })));
+        assert_eq!(Recurrence::parse("每月15号"), Ok(Some(Recurrence::Monthly { day: 15 })));
+        assert_eq!(Recurrence::parse("每3天"), Ok(Some(Recurrence::Every { days: 3 })));
+        assert_eq!(Recurrence::parse("无"), Ok(None));
+        assert!(Recurrence::parse("monthly:32").is_err());
+        assert!(Recurrence::parse("every:0").is_err());
+        assert_eq!(Recurrence::parse("every:36500"), Ok(Some(Recurrence::Every { days: 36500 })));
+        assert!(Recurrence::parse("every:36501").is_err());
+        assert!(Recurrence::parse("every:100000000").is_err());
+        assert!(Recurrence::parse("weekly:xyz").is_err());
+    }
+
+    #[test]
+    fn date_overflow_yields_none() {
+        // 数据文件中可能写入超出 parse 限制的间隔
+        let base = at(2026, 10, 14, 9, 0);
+        assert_eq!(Recurrence::Every { days: u32::MAX }.next_after(base, base), None);
+        assert_eq!(Recurrence::Daily.step(NaiveDate::MAX), None);
+        assert_eq!(Recurrence::Weekly { weekdays: (1..=7).collect() }.step(NaiveDate::MAX), None);
+    }
+
+    #[test]
+    fn daily_and_every_keep_time_of_day() {
+        let base = at(2026, 10, 14, 18, 30);
+        assert_eq!(Recurrence::Daily.next_after(base, base), Some(at(2026, 10, 15, 18, 30)));
+        assert_eq!(Recurrence::Every { days: 3 }.next_after(base, base), Some(at(2026, 10, 17, 18, 30)));
+    }
+
+    #[test]
+    fn skips_instances_already_in_the_past() {
+        let base = at(2026, 10, 1, 9, 0);
+        let now = at(2026, 10, 14, 12, 0);
+        assert_eq!(Recurrence::Daily.next_after(base, now), Some(at(2026, 10, 15, 9, 0)));
+        assert_eq!(Recurrence::Every { days: 5 }.next_after(base, now), Some(at(2026, 10, 16, 9, 0)));
+    }
+
+    #[test]
+    fn weekday_sets() {
+        // 2026-10-14 是周三
+        let base = at(2026, 10, 14, 9, 0);
+        let rule = Recurrence::Weekly { weekdays: vec![1, 4] };
+        assert_eq!(rule.next_after(base, base), Some(at(2026, 10, 15, 9, 0)));
+        assert_eq!(rule.next_after(at(2026, 10, 15, 9, 0), base), Some(at(2026, 10, 19, 9, 0)));
+        let rule = Recurrence::Weekly { weekdays: vec![3] };
+        assert_eq!(rule.next_after(base, base), Some(at(2026, 10, 21, 9, 0)));
+        let rule = Recurrence::Weekly { weekdays: Vec::new() };
+        assert_eq!(rule.next_after(base, base), Some(at(2026, 10, 21, 9, 0)));
+    }
+
+    #[test]
+    fn month_end_is_clamped_and_restored() {
+        let rule = Recurrence::Monthly { day: 31 };
+        let jan = at(2026, 1, 31, 10, 0);
+        let feb = rule.next_after(jan, jan).unwrap();
+        assert_eq!(feb, at(2026, 2, 28, 10, 0));
+        assert_eq!(rule.next_after(feb, feb), Some(at(2026, 3, 31, 10, 0)));
+        assert_eq!(rule.next_after(at(2026, 12, 31, 10, 0), jan), Some(at(2027, 1, 31, 10, 0)));
+        // 闰年的二月
+        assert_eq!(rule.next_after(at(2028, 1, 31, 10, 0), jan), Some(at(2028, 2, 29, 10, 0)));
+    }
+
+    #[test]
+    fn monthly_before_day_stays_in_month() {
+        let rule = Recurrence::Monthly { day: 15 };
+        let base = at(2026, 10, 3, 8, 0);
+        assert_eq!(rule.next_after(base, base), Some(at(2026, 10, 15, 8, 0)));
+    }
+
+    #[test]
+    fn time_in_dst_gap_moves_forward() {
+        // 2026-03-08 02:30 在美国东部时区不存在，其他时区下则是普通的时刻
+        let base = at(2026, 3, 7, 2, 30);
+        let next = Recurrence::Daily.next_after(base, base).and_then(|t| Local.timestamp_opt(t, 0).single()).unwrap();
+        assert_eq!(next.date_naive(), NaiveDate::from_ymd_opt(2026, 3, 8).unwrap());
+        assert!(next.time() >= chrono::NaiveTime::from_hms_opt(2, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn invalid_weekdays_from_file_are_dropped() {
+        let rule: Recurrence = serde_json::from_str(r#"{"kind":"weekly","weekdays":[0,3,9,3,1]}"#).unwrap();
+        assert_eq!(rule, Recurrence::Weekly { weekdays: vec![1, 3] });
+        assert_eq!(rule.label(), "每周一、三");
+        assert_eq!(Recurrence::Weekly { weekdays: vec![0, 8] }.label(), "每周");
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+use crate::todo_mod::todo_list::{Status, Todo, Todos};
+
+/// 待办事项下的检查项
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subtask {
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// 勾选检查项后的结果
+#[derive(Debug, Default)]
+pub struct TickOutcome {
+    /// 所有检查项完成后是否自动完成了父事项
+    pub parent_completed: bool,
+    /// 检查项已全部完成，但父事项仍有未完成的依赖，因此没有自动完成
+    pub blocked: bool,
+    /// 父事项为重复事项时新生成的下一次实例 ID
+    pub next_id: Option<String>,
+}
+
+impl Todo {
+    /// 返回 (已完成数, 总数)，没有检查项时为 None
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        if self.subtasks.is_empty() {
+            return None;
+        }
+        Some((self.subtasks.iter().filter(|s| s.done).count(), self.subtasks.len()))
+    }
+
+    pub fn show_subtasks(&self) {
+        for (i, subtask) in self.subtasks.iter().enumerate() {
+            println!("  {}. [{}] {}", i + 1, if subtask.done { "x" } else { " " }, subtask.text);
+        }
+    }
+
+    // 检查项序号从 1 开始
+    fn subtask_index(&self, number: usize) -> Result<usize, String> {
+        if number >= 1 && number <= self.subtasks.len() {
+            Ok(number - 1)
+        } else {
+            Err(format!("检查项序号 {} 超出范围（共 {} 项）", number, self.subtasks.len()))
+        }
+    }
+}
+
+impl Todos {
+    pub fn add_subtask(&mut self, index: usize, text: &str) -> Result<(), String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(String::from("检查项内容不能为空"));
+        }
+        let todo = &mut self.todos[index];
+        todo.subtasks.push(Subtask { text: text.to_string(), done: false });
+        todo.touch();
+        Ok(())
+    }
+
+    pub fn remove_subtask(&mut self, index: usize, number: usize) -> Result<Subtask, String> {
+        let todo = &mut self.todos[index];
+        let i = todo.subtask_index(number)?;
+        todo.touch();
+        Ok(todo.subtasks.remove(i))
+    }
+
+    /// 勾选或取消勾选检查项；开启了自动完成且全部勾选时，将父事项标记为已完成
+    pub fn tick_subtask(&mut self, index: usize, number: usize, done: bool) -> Result<TickOutcome, String> {
+        let todo = &mut self.todos[index];
+        let i = todo.subtask_index(number)?;
+        todo.subtasks[i].done = done;
+        todo.touch();
+        let mut outcome = TickOutcome::default();
+        if done && todo.auto_complete && !todo.status.is_closed()
+            && todo.subtasks.iter().all(|s| s.done) {
+            if self.is_blocked(index) {
+                outcome.blocked = true;
+                return Ok(outcome);
+            }
+            outcome.parent_completed = true;
+            outcome.next_id = self.set_status(index, Status::Done);
+        }
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo_mod::recurrence::Recurrence;
+
+    fn todos_with(parent: Todo, dependency: Status) -> Todos {
+        let mut todos = Todos::default();
+        todos.todos.push(Todo { id: String::from("dep"), title: String::from("依赖"), status: dependency, ..Default::default() });
+        todos.todos.push(Todo {
+            id: String::from("p"),
+            title: String::from("父事项"),
+            depends_on: vec![String::from("dep")],
+            subtasks: vec![
+                Subtask { text: String::from("一"), done: false },
+                Subtask { text: String::from("二"), done: false },
+            ],
+            ..parent
+        });
+        todos
+    }
+
+    fn auto() -> Todo {
+        Todo { auto_complete: true, ..Default::default() }
+    }
+
+    #[test]
+    fn adds_removes_and_reports_progress() {
+        let mut todos = todos_with(Todo::default(), Status::Done);
+        assert_eq!(todos.todos[0].progress(), None);
+        assert!(todos.add_subtask(1, "  ").is_err());
+        todos.add_subtask(1, " 三 ").unwrap();
+        assert_eq!(todos.todos[1].subtasks[2].text, "三");
+        todos.tick_subtask(1, 3, true).unwrap();
+        assert_eq!(todos.todos[1].progress(), Some((1, 3)));
+        assert_eq!(todos.remove_subtask(1, 1).unwrap().text, "一");
+        assert!(todos.remove_subtask(1, 3).is_err());
+        assert!(todos.tick_subtask(1, 0, true).is_err());
+        assert!(todos.todos[1].updated_time.is_some());
+    }
+
+    #[test]
+    fn last_tick_completes_parent_only_with_auto_complete() {
+        let mut todos = todos_with(Todo::default(), Status::Done);
+        todos.tick_subtask(1, 1, true).unwrap();
+        let outcome = todos.tick_subtask(1, 2, true).unwrap();
+        assert!(!outcome.parent_completed);
+        assert_eq!(todos.todos[1].status, Status::Pending);
+
+        let mut todos = todos_with(auto(), Status::Done);
+        assert!(!todos.tick_subtask(1, 1, true).unwrap().parent_completed);
+        let outcome = todos.tick_subtask(1, 2, true).unwrap();
+        assert!(outcome.parent_completed && !outcome.blocked);
+        assert_eq!(outcome.next_id, None);
+        assert_eq!(todos.todos[1].status, Status::Done);
+        assert!(todos.todos[1].completed_time.is_some());
+
+        // 取消勾选不会撤销完成状态，再次勾选也不会重复完成
+        todos.tick_subtask(1, 2, false).unwrap();
+        assert_eq!(todos.todos[1].status, Status::Done);
+        assert!(!todos.tick_subtask(1, 2, true).unwrap().parent_completed);
+    }
+
+    #[test]
+    fn open_dependency_blocks_auto_complete() {
+        let mut todos = todos_with(auto(), Status::Pending);
+        todos.tick_subtask(1, 1, true).unwrap();
+        let outcome = todos.tick_subtask(1, 2, true).unwrap();
+        assert!(outcome.blocked && !outcome.parent_completed);
+        assert_eq!(todos.todos[1].status, Status::Pending);
+
+        todos.set_status(0, Status::Done);
+        let outcome = todos.tick_subtask(1, 2, true).unwrap();
+        assert!(outcome.parent_completed && !outcome.blocked);
+        assert_eq!(todos.todos[1].status, Status::Done);
+    }
+
+    #[test]
+    fn recurring_parent_spawns_next_instance_with_fresh_subtasks() {
+        let parent = Todo { recurrence: Some(Recurrence::Daily), ..auto() };
+        let mut todos = todos_with(parent, Status::Done);
+        todos.tick_subtask(1, 1, true).unwrap();
+        let next_id = todos.tick_subtask(1, 2, true).unwrap().next_id.expect("应生成下一次实例");
+        let next = todos.todos.iter().find(|t| t.id == next_id).unwrap();
+        assert_eq!(next.status, Status::Pending);
+        assert_eq!(next.recurrence, Some(Recurrence::Daily));
+        assert_eq!(next.progress(), Some((0, 2)));
+        assert_eq!(todos.todos[1].recurrence, None);
+    }
+}
@@ -0,0 +1,556 @@
+use crate::storage_mod::data_file::is_corrupt;
+use crate::cli_mod::args::{usage_error, Args, EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+use crate::todo_mod::depend;
+use crate::todo_mod::export::ExportFormat;
+use crate::todo_mod::deadline::{parse_deadline, DEADLINE_HELP};
+use crate::todo_mod::import::{self, ImportFormat};
+use crate::todo_mod::filter::{Filter, FILTER_HELP};
+use crate::todo_mod::recurrence::{Recurrence, RECURRENCE_HELP};
+use crate::todo_mod::todo_list::{parse_project, parse_tags, Priority, Status, Todo, Todos};
+use crate::todo_mod::view::{ListOptions, Sort};
+
+const USAGE: &str = "\
+用法: small_tools todo <子命令> [选项]
+
+子命令:
+  add  --title <标题> [--content <内容>] [--due <截止时间>] [--priority <P0-P3>]
+       [--tags <标签,...>] [--project <项目>] [--repeat <重复规则>] [--depends <ID,...>]
+      添加待办事项，--depends 指定依赖的事项，这些事项完成前本事项处于阻塞状态
+  list [--all] [--sort <排序方式>] [--tag <标签,...>] [--project <项目>] [--group]
+       [--filter <表达式>] [--ready]
+      显示待办事项，--all 同时显示已完成/已取消的事项，--group 按项目分组，
+      --ready 只显示可以立即开始（没有未完成依赖）的事项
+  ready [list 的其他选项]
+      等同于 list --ready
+  export [--format <csv|md|todotxt|ics>] [--output <文件>] [--sort <排序方式>] [--tag <标签,...>]
+       [--project <项目>] [--filter <表达式>] [--ready] [--events]
+      导出为 CSV、Markdown 清单、todo.txt 或 iCalendar，默认导出全部事项（含已完成），
+      ics 格式加 --events 时为截止时间额外生成日历事件，
+      未指定 --output 时输出到标准输出，未指定 --format 时按文件扩展名推断
+  import <文件> [--format <csv|todotxt|json|ics>] [--map <列名=字段,...>] [--skip-invalid] [--dry-run]
+      从 CSV、todo.txt、iCalendar 的 VTODO 或本工具的 JSON 导入，跳过与已有事项重复的条目（ID 相同，或标题和项目相同）；
+      有无效的行时不导入任何内容，--skip-invalid 跳过无效行继续导入，--dry-run 只检查不保存；
+      CSV 按表头识别列，--map 指定自定义列名，如 --map \"Task Name=title,Notes=content\"
+  search <关键字> [--regex] [list 的其他选项]
+      按标题和内容搜索（不区分大小写），--regex 表示按正则表达式匹配
+  edit <ID|序号> [--title <新标题>] [--content <内容>] [--due <截止时间>] [--priority <P0-P3>]
+       [--tags <标签,...>] [--project <项目>] [--repeat <重复规则>] [--depends <ID,...>]
+      编辑待办事项，未指定的字段保持不变，标签、项目或依赖传空字符串表示清空
+  rm <ID|序号>               删除待办事项
+  done <ID|序号> [--force]   标记为已完成，重复事项会自动生成下一次实例；
+                             依赖的事项未完成时拒绝执行，--force 强制完成
+  undone <ID|序号>           重新标记为待处理
+  status <ID|序号> <状态> [--force]
+                             更改状态（pending/in-progress/done/cancelled）
+  sub <ID|序号> <操作>       管理检查项:
+      add <内容>             添加检查项
+      tick <序号>            勾选检查项，开启自动完成且全部勾选时将事项标记为已完成
+      untick <序号>          取消勾选检查项
+      rm <序号>              删除检查项
+      auto on|off            开启/关闭全部勾选后自动完成
+
+排序方式: priority、due、created、title，加 :desc 或前缀 - 表示降序，如 --sort due:desc";
+
+/// 非交互式的待办事项命令入口，返回进程退出码
+pub fn todo_cmd(args: &[String]) -> i32 {
+    let Some((sub, rest)) = args.split_first() else {
+        eprintln!("{}", USAGE);
+        return EXIT_USAGE;
+    };
+    match sub.as_str() {
+        "add" => add(rest),
+        "list" | "ls" => list(rest, false),
+        "search" | "find" => list(rest, true),
+        "export" => export(rest),
+        "import" => import_file(rest),
+        "ready" | "next" => list(&[rest, &[String::from("--ready")]].concat(), false),
+        "edit" => edit(rest),
+        "rm" | "remove" => remove(rest),
+        "done" => set_status(rest, Some(Status::Done)),
+        "undone" => set_status(rest, Some(Status::Pending)),
+        "status" => set_status(rest, None),
+        "sub" | "subtask" => subtask(rest),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            println!();
+            println!("{}", DEADLINE_HELP);
+            println!();
+            println!("{}", RECURRENCE_HELP);
+            println!();
+            println!("{}", FILTER_HELP);
+            EXIT_OK
+        },
+        _ => usage_error(&format!("未知的子命令: {}", sub), USAGE),
+    }
+}
+
+fn add(args: &[String]) -> i32 {
+    let args = match Args::parse(args, &["title", "content", "due", "priority", "tags", "project", "repeat", "depends"], &[]) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e, USAGE),
+    };
+    let Some(title) = args.opt("title").map(str::trim).filter(|t| !t.is_empty()) else {
+        return usage_error("缺少 --title", USAGE);
+    };
+    let now = chrono::Local::now();
+    let dead_line = match args.opt("due").map(|due| parse_deadline(due, now)) {
+        Some(Ok(dead_line)) => dead_line,
+        Some(Err(e)) => return usage_error(&e, USAGE),
+        None => None,
+    };
+    let priority = match args.opt("priority").map(Priority::parse) {
+        Some(Some(priority)) => priority,
+        Some(None) => return usage_error("无效的优先级，可选值: P0/P1/P2/P3", USAGE),
+        None => Priority::default(),
+    };
+    let recurrence = match args.opt("repeat").map(Recurrence::parse) {
+        Some(Ok(recurrence)) => recurrence,
+        Some(Err(e)) => return usage_error(&e, USAGE),
+        None => None,
+    };
+
+    let mut todos = match load() {
+        Ok(todos) => todos,
+        Err(code) => return code,
+    };
+    let depends_on = match todos.parse_dependencies(None, args.opt("depends").unwrap_or_default()) {
+        Ok(depends_on) => depends_on,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    let id = todos.add_todo(Todo {
+        title: title.to_string(),
+        content: args.opt("content").unwrap_or_default().to_string(),
+        create_time: now.timestamp(),
+        dead_line,
+        priority,
+        tags: args.opt("tags").map(parse_tags).unwrap_or_default(),
+        project: args.opt("project").and_then(parse_project),
+        recurrence,
+        depends_on,
+        ..Default::default()
+    });
+    if let Err(code) = save(&mut todos) {
+        return code;
+    }
+    println!("✅ 已添加待办事项: {} (ID: {})", title, id);
+    EXIT_OK
+}
+
+// search 为 true 时第一个位置参数作为搜索关键字
+fn list(args: &[String], search: bool) -> i32 {
+    let args = match Args::parse(args, &["sort", "tag", "project", "filter"], &["all", "group", "regex", "ready"]) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e, USAGE),
+    };
+    let mut filter = match Filter::parse(args.opt("filter").unwrap_or_default()) {
+        Ok(filter) => filter,
+        Err(e) => return usage_error(&format!("筛选表达式有误: {}", e), USAGE),
+    };
+    if search {
+        let Some(keyword) = args.positional(0) else {
+            return usage_error("缺少搜索关键字", USAGE);
+        };
+        filter = match Filter::search(keyword, args.flag("regex")) {
+            Ok(search) => search.and(filter),
+            Err(e) => return usage_error(&e, USAGE),
+        };
+    }
+    let sort = match args.opt("sort").map(Sort::parse) {
+        Some(Some(sort)) => Some(sort),
+        Some(None) => return usage_error("无效的排序方式", USAGE),
+        None => None,
+    };
+    let opts = ListOptions {
+        show_all: args.flag("all"),
+        sort,
+        tags: args.opt("tag").map(parse_tags).unwrap_or_default(),
+        project: args.opt("project").and_then(parse_project),
+        group_by_project: args.flag("group"),
+        filter: if filter.is_empty() { None } else { Some(filter) },
+        ready_only: args.flag("ready"),
+    };
+    match load() {
+        Ok(todos) => {
+            todos.show_todos(&opts);
+            EXIT_OK
+        },
+        Err(code) => code,
+    }
+}
+
+fn export(args: &[String]) -> i32 {
+    let args = match Args::parse(args, &["format", "output", "sort", "tag", "project", "filter"], &["ready", "events"]) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e, USAGE),
+    };
+    let output = args.opt("output").filter(|o| !o.is_empty() && *o != "-");
+    let format = match (args.opt("format"), output) {
+        (Some(format), _) => match ExportFormat::parse(format) {
+            Some(format) => format,
+            None => return usage_error("无效的导出格式，可选值: csv/md/todotxt/ics", USAGE),
+        },
+        (None, Some(path)) => match ExportFormat::from_path(path) {
+            Some(format) => format,
+            None => return usage_error("无法根据文件扩展名判断导出格式，请使用 --format 指定", USAGE),
+        },
+        (None, None) => return usage_error("缺少 --format", USAGE),
+    };
+    let format = match format {
+        ExportFormat::Ics { .. } => ExportFormat::Ics { events: args.flag("events") },
+        format => format,
+    };
+    let filter = match Filter::parse(args.opt("filter").unwrap_or_default()) {
+        Ok(filter) => filter,
+        Err(e) => return usage_error(&format!("筛选表达式有误: {}", e), USAGE),
+    };
+    let sort = match args.opt("sort").map(Sort::parse) {
+        Some(Some(sort)) => Some(sort),
+        Some(None) => return usage_error("无效的排序方式", USAGE),
+        None => None,
+    };
+    let opts = ListOptions {
+        show_all: true,
+        sort,
+        tags: args.opt("tag").map(parse_tags).unwrap_or_default(),
+        project: args.opt("project").and_then(parse_project),
+        group_by_project: false,
+        filter: if filter.is_empty() { None } else { Some(filter) },
+        ready_only: args.flag("ready"),
+    };
+
+    let todos = match load() {
+        Ok(todos) => todos,
+        Err(code) => return code,
+    };
+    let entries = opts.select(&todos.todos);
+    let content = match format.render(&entries) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("❌ 导出失败: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, content) {
+                eprintln!("❌ 写入 {} 失败: {}", path, e);
+                return EXIT_FAILURE;
+            }
+            println!("✅ 已导出 {} 条待办事项到 {}", entries.len(), path);
+        },
+        None => print!("{}", content),
+    }
+    EXIT_OK
+}
+
+fn import_file(args: &[String]) -> i32 {
+    let args = match Args::parse(args, &["format", "map"], &["skip-invalid", "dry-run"]) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e, USAGE),
+    };
+    let Some(path) = args.positional(0) else {
+        return usage_error("缺少要导入的文件", USAGE);
+    };
+    let format = match args.opt("format").map(ImportFormat::parse).unwrap_or_else(|| ImportFormat::from_path(path)) {
+        Some(format) => format,
+        None => return usage_error("无法判断导入格式，请使用 --format 指定 csv/todotxt/json/ics", USAGE),
+    };
+    let mapping = match import::parse_mapping(args.opt("map").unwrap_or_default()) {
+        Ok(mapping) => mapping,
+        Err(e) => return usage_error(&e, USAGE),
+    };
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("❌ 读取 {} 失败: {}", path, e);
+            return EXIT_FAILURE;
+        }
+    };
+
+    let (items, errors) = import::parse(format, &text, &mapping);
+    for error in &errors {
+        eprintln!("❌ {}:{}: {}", path, error.line, error.message);
+    }
+    if !errors.is_empty() && !args.flag("skip-invalid") {
+        eprintln!("共 {} 处错误，未导入任何内容；使用 --skip-invalid 跳过无效行", errors.len());
+        return EXIT_FAILURE;
+    }
+    let mut todos = match load() {
+        Ok(todos) => todos,
+        Err(code) => return code,
+    };
+    let report = todos.import(items);
+    for (line, title) in &report.duplicates {
+        println!("⏭️ {}:{}: 已存在，跳过: {}", path, line, title);
+    }
+    for warning in &report.warnings {
+        println!("⚠️ {}", warning);
+    }
+    if args.flag("dry-run") {
+        println!("（试运行）可导入 {} 条，跳过重复 {} 条，无效 {} 条", report.added, report.duplicates.len(), errors.len());
+        return EXIT_OK;
+    }
+    if report.added > 0
+        && let Err(code) = save(&mut todos) {
+        return code;
+    }
+    println!("✅ 已导入 {} 条，跳过重复 {} 条，无效 {} 条", report.added, report.duplicates.len(), errors.len());
+    EXIT_OK
+}
+
+fn edit(args: &[String]) -> i32 {
+    let args = match Args::parse(args, &["title", "content", "due", "priority", "tags", "project", "repeat", "depends"], &[]) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e, USAGE),
+    };
+    let Some(target) = args.positional(0) else {
+        return usage_error("缺少要编辑的待办 ID 或序号", USAGE);
+    };
+    if ["title", "content", "due", "priority", "tags", "project", "repeat", "depends"].iter().all(|name| args.opt(name).is_none()) {
+        return usage_error("未指定要修改的字段", USAGE);
+    }
+    let dead_line = match args.opt("due").map(|due| parse_deadline(due, chrono::Local::now())) {
+        Some(Ok(dead_line)) => Some(dead_line),
+        Some(Err(e)) => return usage_error(&e, USAGE),
+        None => None,
+    };
+    let priority = match args.opt("priority").map(Priority::parse) {
+        Some(Some(priority)) => Some(priority),
+        Some(None) => return usage_error("无效的优先级，可选值: P0/P1/P2/P3", USAGE),
+        None => None,
+    };
+    let recurrence = match args.opt("repeat").map(Recurrence::parse) {
+        Some(Ok(recurrence)) => Some(recurrence),
+        Some(Err(e)) => return usage_error(&e, USAGE),
+        None => None,
+    };
+
+    let mut todos = match load() {
+        Ok(todos) => todos,
+        Err(code) => return code,
+    };
+    let index = match todos.resolve(target) {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    let depends_on = match args.opt("depends").map(|deps| todos.parse_dependencies(Some(index), deps)) {
+        Some(Ok(depends_on)) => Some(depends_on),
+        Some(Err(e)) => {
+            eprintln!("❌ {}", e);
+            return EXIT_FAILURE;
+        },
+        None => None,
+    };
+    let todo = &mut todos.todos[index];
+    if let Some(title) = args.opt("title") {
+        todo.title = title.trim().to_string();
+    }
+    if let Some(content) = args.opt("content") {
+        todo.content = content.to_string();
+    }
+    if let Some(dead_line) = dead_line {
+        todo.dead_line = dead_line;
+    }
+    if let Some(priority) = priority {
+        todo.priority = priority;
+    }
+    if let Some(tags) = args.opt("tags") {
+        todo.tags = parse_tags(tags);
+    }
+    if let Some(project) = args.opt("project") {
+        todo.project = parse_project(project);
+    }
+    if let Some(recurrence) = recurrence {
+        todo.recurrence = recurrence;
+    }
+    if let Some(depends_on) = depends_on {
+        todo.depends_on = depends_on;
+    }
+    todo.touch();
+    if let Err(code) = save(&mut todos) {
+        return code;
+    }
+    println!("✅ 成功更新待办事项!");
+    EXIT_OK
+}
+
+fn remove(args: &[String]) -> i32 {
+    let args = match Args::parse(args, &[], &[]) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e, USAGE),
+    };
+    let Some(target) = args.positional(0) else {
+        return usage_error("缺少要删除的待办 ID 或序号", USAGE);
+    };
+
+    let mut todos = match load() {
+        Ok(todos) => todos,
+        Err(code) => return code,
+    };
+    let index = match todos.resolve(target) {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    let removed = todos.todos.remove(index);
+    if let Err(code) = save(&mut todos) {
+        return code;
+    }
+    println!("✅ 成功删除待办事项: {}", removed.title);
+    EXIT_OK
+}
+
+// status 为 None 时从第二个位置参数读取目标状态
+fn set_status(args: &[String], status: Option<Status>) -> i32 {
+    let args = match Args::parse(args, &[], &["force"]) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e, USAGE),
+    };
+    let Some(target) = args.positional(0) else {
+        return usage_error("缺少待办 ID 或序号", USAGE);
+    };
+    let status = match status {
+        Some(status) => status,
+        None => match args.positional(1).map(Status::parse) {
+            Some(Some(status)) => status,
+            Some(None) => return usage_error("无效的状态，可选值: pending/in-progress/done/cancelled", USAGE),
+            None => return usage_error("缺少目标状态", USAGE),
+        },
+    };
+
+    let mut todos = match load() {
+        Ok(todos) => todos,
+        Err(code) => return code,
+    };
+    let index = match todos.resolve(target) {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    if status == Status::Done && todos.todos[index].status != Status::Done {
+        let blockers = todos.blockers(index);
+        if !blockers.is_empty() {
+            if !args.flag("force") {
+                eprintln!("❌ '{}' 依赖的事项尚未完成: {}", todos.todos[index].title, depend::describe(&blockers));
+                eprintln!("使用 --force 强制标记为已完成");
+                return EXIT_FAILURE;
+            }
+            eprintln!("⚠️ 依赖的事项尚未完成: {}", depend::describe(&blockers));
+        }
+    }
+    let next = todos.set_status(index, status);
+    if let Err(code) = save(&mut todos) {
+        return code;
+    }
+    println!("✅ 已将 '{}' 标记为{}", todos.todos[index].title, status.label());
+    if let Some(id) = next {
+        println!("🔁 已生成下一次重复事项 (ID: {})", id);
+    }
+    EXIT_OK
+}
+
+fn subtask(args: &[String]) -> i32 {
+    let args = match Args::parse(args, &[], &[]) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e, USAGE),
+    };
+    let (Some(target), Some(action)) = (args.positional(0), args.positional(1)) else {
+        return usage_error("用法: todo sub <ID|序号> <add|tick|untick|rm|auto> [参数]", USAGE);
+    };
+    let arg = args.positionals()[2..].join(" ");
+    let number = || arg.trim().parse::<usize>().map_err(|_| format!("无效的检查项序号: {}", arg));
+
+    let mut todos = match load() {
+        Ok(todos) => todos,
+        Err(code) => return code,
+    };
+    let index = match todos.resolve(target) {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    let mut messages = Vec::new();
+    let result = match action {
+        "add" => todos.add_subtask(index, &arg).map(|_| messages.push(String::from("✅ 已添加检查项"))),
+        "tick" | "done" => number().and_then(|n| todos.tick_subtask(index, n, true)).map(|outcome| {
+            messages.push(String::from("✅ 已勾选检查项"));
+            if outcome.blocked {
+                messages.push(String::from("⚠️ 检查项已全部完成，但仍有未完成的依赖，未自动完成"));
+            }
+            if outcome.parent_completed {
+                messages.push(format!("✅ 检查项已全部完成，'{}' 已标记为已完成", todos.todos[index].title));
+            }
+            if let Some(id) = outcome.next_id {
+                messages.push(format!("🔁 已生成下一次重复事项 (ID: {})", id));
+            }
+        }),
+        "untick" | "undone" => number().and_then(|n| todos.tick_subtask(index, n, false))
+            .map(|_| messages.push(String::from("✅ 已取消勾选检查项"))),
+        "rm" | "remove" => number().and_then(|n| todos.remove_subtask(index, n))
+            .map(|s| messages.push(format!("✅ 已删除检查项: {}", s.text))),
+        "auto" => {
+            let auto = match arg.trim() {
+                "on" => true,
+                "off" => false,
+                _ => return usage_error("auto 的取值必须是 on 或 off", USAGE),
+            };
+            todos.todos[index].auto_complete = auto;
+            todos.todos[index].touch();
+            messages.push(String::from(if auto { "✅ 已开启自动完成" } else { "✅ 已关闭自动完成" }));
+            Ok(())
+        },
+        _ => return usage_error(&format!("未知的检查项操作: {}", action), USAGE),
+    };
+    if let Err(e) = result {
+        eprintln!("❌ {}", e);
+        return EXIT_FAILURE;
+    }
+    if let Err(code) = save(&mut todos) {
+        return code;
+    }
+    for message in messages {
+        println!("{}", message);
+    }
+    if let Some((done, total)) = todos.todos[index].progress() {
+        println!("{} [{}/{}]", todos.todos[index].title, done, total);
+        todos.todos[index].show_subtasks();
+    }
+    EXIT_OK
+}
+
+// 读取失败时直接退出，避免后续保存覆盖原有数据
+fn load() -> Result<Todos, i32> {
+    let mut todos = Todos::default();
+    todos.lock().map_err(|e| {
+        eprintln!("❌ 无法锁定待办数据文件: {}", e);
+        EXIT_FAILURE
+    })?;
+    todos.load_todos().map_err(|e| {
+        eprintln!("❌ 读取待办数据失败: {}", e);
+        if is_corrupt(&e) {
+            eprintln!("未做任何修改；请手动修复该文件，或在交互模式中打开待办事项列表，备份损坏的文件后重新开始");
+        }
+        EXIT_FAILURE
+    })?;
+    Ok(todos)
+}
+
+fn save(todos: &mut Todos) -> Result<(), i32> {
+    todos.save_todos().map_err(|e| {
+        eprintln!("❌ 保存待办数据失败: {}", e);
+        EXIT_FAILURE
+    })
+}
+
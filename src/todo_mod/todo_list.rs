@@ -1,130 +1,484 @@
+use chrono::TimeZone;
 use serde::{ Deserialize, Serialize };
-use std::path::Path;
-use std::fs::File;
-use std::io::{ Write };
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
-struct Todo {
+use crate::storage_mod::data_file::{is_conflict, DataFile};
+use crate::storage_mod::schema::{self, Schema};
+use crate::todo_mod::depend;
+use crate::todo_mod::deadline::{describe_remaining, format_deadline, parse_deadline, DEADLINE_HELP};
+use crate::todo_mod::export::ExportFormat;
+use crate::todo_mod::import::{self, ImportFormat};
+use crate::todo_mod::filter::{Filter, FILTER_HELP};
+use crate::todo_mod::recurrence::{Recurrence, RECURRENCE_HELP};
+use crate::todo_mod::subtask::Subtask;
+use crate::todo_mod::view::{self, DueSummary, ListOptions, Sort};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    #[default]
+    Pending,
+    InProgress,
+    Done,
+    Cancelled,
+}
+
+impl Status {
+    pub fn parse(input: &str) -> Option<Status> {
+        match input.trim().to_lowercase().as_str() {
+            "pending" | "todo" | "待处理" => Some(Status::Pending),
+            "in-progress" | "in_progress" | "doing" | "进行中" => Some(Status::InProgress),
+            "done" | "finished" | "已完成" => Some(Status::Done),
+            "cancelled" | "canceled" | "已取消" => Some(Status::Cancelled),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::Pending => "待处理",
+            Status::InProgress => "进行中",
+            Status::Done => "已完成",
+            Status::Cancelled => "已取消",
+        }
+    }
+
+    /// 已完成或已取消的事项默认不在列表中显示
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Status::Done | Status::Cancelled)
+    }
+}
+
+/// 优先级，P0 最高、P3 最低
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Priority {
+    P0,
+    P1,
+    #[default]
+    P2,
+    P3,
+}
+
+impl Priority {
+    pub fn parse(input: &str) -> Option<Priority> {
+        match input.trim().to_lowercase().as_str() {
+            "p0" | "0" => Some(Priority::P0),
+            "p1" | "1" => Some(Priority::P1),
+            "p2" | "2" => Some(Priority::P2),
+            "p3" | "3" => Some(Priority::P3),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Priority::P0 => "P0",
+            Priority::P1 => "P1",
+            Priority::P2 => "P2",
+            Priority::P3 => "P3",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Todo {
+    #[serde(default)]
+    pub id: String,
     pub title: String,
     pub content: String,
     pub create_time: i64,
-    pub dead_line: i64
+    /// 为 None 时表示没有截止时间
+    #[serde(default)]
+    pub dead_line: Option<i64>,
+    #[serde(default)]
+    pub status: Status,
+    #[serde(default)]
+    pub completed_time: Option<i64>,
+    /// 最近一次修改的时间，创建后从未修改时为 None
+    #[serde(default)]
+    pub updated_time: Option<i64>,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    /// 重复规则，只保存在尚未完成的那一次实例上
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(default)]
+    pub subtasks: Vec<Subtask>,
+    /// 检查项全部勾选后自动将本事项标记为已完成
+    #[serde(default)]
+    pub auto_complete: bool,
+    /// 依赖的事项 ID，这些事项全部完成前本事项处于阻塞状态
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// 从日历软件导入时的原始 UID，导出 .ics 时沿用
+    #[serde(default)]
+    pub uid: Option<String>,
+}
+
+/// 解析以逗号或空白分隔的标签列表，去掉 `#` 前缀和重复项
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(|c: char| c == ',' || c == '，' || c.is_whitespace()) {
+        let tag = tag.trim().trim_start_matches('#');
+        if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// 空字符串表示不属于任何项目
+pub fn parse_project(input: &str) -> Option<String> {
+    let project = input.trim();
+    if project.is_empty() { None } else { Some(project.to_string()) }
+}
+
+const DATA_FILE: &str = "data.json";
+/// data.json 的版本：0 为不带外层结构的 Todo 数组，1 起包在 `{"version", "data"}` 中
+pub const SCHEMA: Schema = Schema { migrations: &[schema::wrap] };
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Todos {
+    pub todos: Vec<Todo>,
+    #[serde(skip)]
+    file: DataFile,
+    /// 交互模式下用户选择暂不保存的修改尚未写入文件
+    #[serde(skip)]
+    unsaved: bool,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
-struct Todos {
-    pub todos: Vec<Todo>
+impl Default for Todos {
+    fn default() -> Self {
+        Self { todos: Vec::new(), file: DataFile::new(DATA_FILE, SCHEMA), unsaved: false }
+    }
 }
 
 impl Todo {
-    fn show(&self) {
-        let ct = chrono::DateTime::from_timestamp(self.create_time, 0)
-            .expect("Failed to convert create_time timestamp");
-        let ddl = chrono::DateTime::from_timestamp(self.dead_line, 0)
-            .expect("Failed to convert dead_line timestamp");
-        println!("标题: {}", self.title);
+    pub fn show(&self) {
+        // 手工编辑过的数据文件中可能有超出范围的时间戳，显示原始值而不是中断
+        let ct = chrono::Local.timestamp_opt(self.create_time, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| self.create_time.to_string());
+        let now = chrono::Local::now().timestamp();
+        let urgency = view::urgency(self, now);
+        let mut ddl = format_deadline(self.dead_line);
+        if let Some(d) = self.dead_line.filter(|_| !self.status.is_closed()) {
+            ddl = format!("{}（{}）", ddl, describe_remaining(d, now));
+        }
+        println!("ID: {}", self.id);
+        println!("标题: {}", view::paint(&self.title, urgency));
         println!("内容: {}", self.content);
-        println!("创建时间: {}", ct.format("%Y-%m-%d %H:%M:%S"));
-        println!("截止时间: {}", ddl.format("%Y-%m-%d %H:%M:%S"));
+        println!("创建时间: {}", ct);
+        println!("截止时间: {}", view::paint(&ddl, urgency));
+        if let Some(updated) = self.updated_time.and_then(|t| chrono::Local.timestamp_opt(t, 0).single()) {
+            println!("更新时间: {}", updated.format("%Y-%m-%d %H:%M:%S"));
+        }
+        println!("优先级: {}", self.priority.label());
+        if let Some(project) = &self.project {
+            println!("项目: {}", project);
+        }
+        if !self.tags.is_empty() {
+            println!("标签: {}", self.tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" "));
+        }
+        if let Some(recurrence) = &self.recurrence {
+            println!("重复: {}", recurrence.label());
+        }
+        if !self.depends_on.is_empty() {
+            println!("依赖: {}", self.depends_on.join(", "));
+        }
+        if let Some((done, total)) = self.progress() {
+            let auto = if self.auto_complete { "（全部完成后自动完成）" } else { "" };
+            println!("检查项: [{}/{}]{}", done, total, auto);
+            self.show_subtasks();
+        }
+        println!("状态: {}", self.status.label());
+        if let Some(done) = self.completed_time.and_then(|t| chrono::Local.timestamp_opt(t, 0).single()) {
+            println!("完成时间: {}", done.format("%Y-%m-%d %H:%M:%S"));
+        }
         println!("────────────────────────────────────────");
     }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim_start_matches('#');
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// 记录修改时间，创建时间保持不变
+    pub fn touch(&mut self) {
+        self.updated_time = Some(chrono::Local::now().timestamp());
+    }
+
+    /// 更新状态，进入已完成/已取消时记录完成时间，重新打开时清除
+    pub fn set_status(&mut self, status: Status) {
+        if status.is_closed() {
+            if !self.status.is_closed() || self.completed_time.is_none() {
+                self.completed_time = Some(chrono::Local::now().timestamp());
+            }
+        } else {
+            self.completed_time = None;
+        }
+        self.status = status;
+    }
 }
 
+// 菜单项沿用全大写的命名
+#[allow(clippy::upper_case_acronyms)]
 enum Handler {
     INSERT,
     EDIT,
     REMOVE,
     SHOW,
+    DONE,
+    STATUS,
+    ALL,
+    PRIORITY,
+    SORT,
+    TAG,
+    FILTER,
+    GROUP,
+    SEARCH,
+    SUBTASK,
+    DEPEND,
+    READY,
+    EXPORT,
+    IMPORT,
 }
 
 impl Handler {
 
-    fn analyse_flag(flag: &String) -> Handler{
+    fn analyse_flag(flag: &str) -> Handler{
         match flag.trim().to_lowercase().as_str() {
             "1" | "insert" | "add" => Handler::INSERT,
             "2" | "edit" => Handler::EDIT,
             "3" | "remove" | "delete" => Handler::REMOVE,
             "4" | "show" | "list" => Handler::SHOW,
+            "5" | "done" | "complete" => Handler::DONE,
+            "6" | "status" => Handler::STATUS,
+            "7" | "all" => Handler::ALL,
+            "8" | "priority" => Handler::PRIORITY,
+            "9" | "sort" => Handler::SORT,
+            "10" | "tag" | "project" => Handler::TAG,
+            "11" | "filter" => Handler::FILTER,
+            "12" | "group" => Handler::GROUP,
+            "13" | "search" | "find" => Handler::SEARCH,
+            "14" | "sub" | "subtask" | "check" => Handler::SUBTASK,
+            "15" | "depend" | "deps" => Handler::DEPEND,
+            "16" | "ready" | "next" => Handler::READY,
+            "17" | "export" => Handler::EXPORT,
+            "18" | "import" => Handler::IMPORT,
             _ => {
                 println!("无效选项，显示待办事项列表");
                 Handler::SHOW
             }
         }
     }
+
+    // 会修改并保存数据的操作，执行期间持有数据文件的锁
+    fn modifies(&self) -> bool {
+        matches!(
+            self,
+            Handler::INSERT | Handler::EDIT | Handler::REMOVE | Handler::DONE | Handler::STATUS
+                | Handler::PRIORITY | Handler::TAG | Handler::SUBTASK | Handler::DEPEND | Handler::IMPORT
+        )
+    }
 }
 
 impl Todos {
 
-    fn save_todos(&self) -> std::io::Result<()>{
-        let path = if cfg!(windows) {
-            // Windows系统使用AppData目录
-            dirs::data_local_dir().map(|mut p| {
-                p.push("SmallTool");
-                p.push("data.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取AppData目录，则使用当前目录
-                Path::new("data.json").to_path_buf()
-            })
-        } else {
-            // 非Windows系统保持原逻辑
-            dirs::data_dir().map(|mut p| {
-                p.push("small_tools");
-                p.push("data.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取数据目录，则使用当前目录
-                Path::new("data.json").to_path_buf()
-            })
-        };
-        
-        // 确保目录存在
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        
-        let mut file = File::create(path)?;
-        let json_data = serde_json::to_string_pretty(&self.todos).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        file.write_all(json_data.as_bytes())?;
-        file.flush()?;
+    /// 保存到 data.json；文件在读取后被其他实例修改过时返回冲突错误而不覆盖
+    pub fn save_todos(&mut self) -> std::io::Result<()> {
+        self.write_todos(false)
+    }
+
+    /// 忽略其他实例的修改，直接以当前内容覆盖
+    pub fn overwrite_todos(&mut self) -> std::io::Result<()> {
+        self.write_todos(true)
+    }
+
+    fn write_todos(&mut self, force: bool) -> std::io::Result<()> {
+        self.file.write_json(&self.todos, force)?;
+        self.unsaved = false;
         Ok(())
     }
 
-        fn load_todos(&mut self) -> std::io::Result<()> {
-        let path = if cfg!(windows) {
-            // Windows系统使用AppData目录
-            dirs::data_local_dir().map(|mut p| {
-                p.push("SmallTool");
-                p.push("data.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取AppData目录，则使用当前目录
-                Path::new("data.json").to_path_buf()
-            })
-        } else {
-            // 非Windows系统保持原逻辑
-            dirs::data_dir().map(|mut p| {
-                p.push("small_tools");
-                p.push("data.json");
-                p
-            }).unwrap_or_else(|| {
-                // 如果无法获取数据目录，则使用当前目录
-                Path::new("data.json").to_path_buf()
-            })
+    /// 在本实例结束前一直持有数据文件的锁，供命令行的一次性读写使用
+    pub fn lock(&mut self) -> std::io::Result<()> {
+        self.file.lock()
+    }
+
+    pub fn load_todos(&mut self) -> std::io::Result<()> {
+        // 文件不存在时视为空列表，首次保存时再创建
+        self.todos = self.file.read_json()?.unwrap_or_default();
+        self.unsaved = false;
+        // 旧数据没有 ID，补齐后立即写回，保证 ID 在之后的运行中保持不变
+        if self.assign_missing_ids() {
+            self.save_todos()?;
+        }
+        Ok(())
+    }
+
+    /// 读取失败时询问是否备份损坏的文件并以空列表继续
+    pub fn recover(&mut self, error: &std::io::Error) -> bool {
+        self.todos.clear();
+        self.file.confirm_recovery(error)
+    }
+
+    // 交互模式下修改前加锁，直到 `end_edit`；文件已被其他实例修改时先重新加载，
+    // 但有暂不保存的修改时保留，由保存时的冲突提示处理
+    fn begin_edit(&mut self) -> bool {
+        let stale = match self.file.lock_for_edit() {
+            Ok(stale) => stale,
+            Err(e) => {
+                println!("读取数据失败: {}", e);
+                return false;
+            }
         };
-        
-        if !path.exists() {
-            // 确保目录存在
-            if let Some(parent) = path.parent() {
-                std::fs::create_dir_all(parent)?;
+        if stale && !self.unsaved {
+            if let Err(e) = self.load_todos() {
+                self.file.unlock();
+                println!("重新加载失败: {}", e);
+                return false;
+            }
+            println!("数据已被其他实例修改，已重新加载");
+        }
+        true
+    }
+
+    fn end_edit(&mut self) {
+        self.file.unlock();
+    }
+
+    // 交互模式下保存，文件已被其他实例修改时由用户选择覆盖、重新加载或暂不保存
+    fn persist(&mut self) {
+        let error = match self.save_todos() {
+            Ok(()) => return,
+            Err(e) => e,
+        };
+        self.unsaved = true;
+        if !is_conflict(&error) {
+            println!("保存失败: {}", error);
+            return;
+        }
+        println!("警告：{}", error);
+        println!("输入 o 用当前内容覆盖，输入 r 放弃本次修改并重新加载，其他键暂不保存：");
+        match self.read_user_input("读取输入失败").trim().to_lowercase().as_str() {
+            "o" => match self.overwrite_todos() {
+                Ok(()) => println!("已覆盖保存"),
+                Err(e) => println!("保存失败: {}", e),
+            },
+            "r" => match self.load_todos() {
+                Ok(()) => println!("已重新加载"),
+                Err(e) => println!("重新加载失败: {}", e),
+            },
+            _ => println!("本次修改尚未保存，下次保存时会再次提示"),
+        }
+    }
+
+    /// 添加待办事项并为其分配 ID，返回新分配的 ID
+    pub fn add_todo(&mut self, mut todo: Todo) -> String {
+        todo.id = self.generate_id();
+        let id = todo.id.clone();
+        self.todos.push(todo);
+        id
+    }
+
+    fn assign_missing_ids(&mut self) -> bool {
+        let mut changed = false;
+        for i in 0..self.todos.len() {
+            if self.todos[i].id.is_empty() {
+                self.todos[i].id = self.generate_id();
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    // 生成 6 位短 ID，首字符固定为字母，避免与列表序号混淆
+    fn generate_id(&self) -> String {
+        const LETTERS: &[u8] = b"abcdefghjkmnpqrstuvwxyz";
+        const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+        let state = RandomState::new();
+        let mut counter: u64 = 0;
+        loop {
+            let mut seed = state.hash_one((chrono::Local::now().timestamp_nanos_opt(), counter));
+            let mut id = String::with_capacity(6);
+            id.push(LETTERS[(seed % LETTERS.len() as u64) as usize] as char);
+            for _ in 1..6 {
+                seed /= LETTERS.len() as u64;
+                id.push(ALPHABET[(seed % ALPHABET.len() as u64) as usize] as char);
+            }
+            if !self.todos.iter().any(|t| t.id == id) {
+                return id;
+            }
+            counter += 1;
+        }
+    }
+
+    /// 更改状态并记录修改时间；重复事项被标记为完成时自动生成下一次实例，返回其 ID
+    pub fn set_status(&mut self, index: usize, status: Status) -> Option<String> {
+        let todo = &mut self.todos[index];
+        let was_done = todo.status == Status::Done;
+        todo.set_status(status);
+        todo.touch();
+        if status != Status::Done || was_done {
+            return None;
+        }
+        // 下一次的截止时间超出可表示的范围时不生成新实例
+        let now = chrono::Local::now().timestamp();
+        let base = todo.dead_line.unwrap_or(now);
+        let dead_line = todo.recurrence.as_ref()?.next_after(base, now)?;
+        // 重复规则随之转移到下一次实例，避免同一实例反复完成时重复生成
+        let recurrence = todo.recurrence.take()?;
+        let next = Todo {
+            id: String::new(),
+            create_time: now,
+            dead_line: Some(dead_line),
+            status: Status::Pending,
+            completed_time: None,
+            updated_time: None,
+            recurrence: Some(recurrence),
+            uid: None,
+            subtasks: todo.subtasks.iter()
+                .map(|s| Subtask { done: false, ..s.clone() })
+                .collect(),
+            ..todo.clone()
+        };
+        Some(self.add_todo(next))
+    }
+
+    /// 根据 ID 或 `show_todos` 中显示的序号定位待办事项
+    pub fn resolve(&self, key: &str) -> Result<usize, String> {
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(String::from("ID 或序号不能为空"));
+        }
+        if let Some(index) = self.todos.iter().position(|t| t.id.eq_ignore_ascii_case(key)) {
+            return Ok(index);
+        }
+        match key.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= self.todos.len() => Ok(n - 1),
+            Ok(n) => Err(format!("序号 {} 超出范围（共 {} 条）", n, self.todos.len())),
+            Err(_) => Err(format!("未找到 ID 为 '{}' 的待办事项", key)),
+        }
+    }
+
+    // 交互式读取 ID 或序号，找不到时打印原因并返回 None
+    fn prompt_index(&self) -> Option<usize> {
+        println!("输入待办 ID 或序号：");
+        let key = self.read_user_input("读取输入失败");
+        match self.resolve(&key) {
+            Ok(index) => Some(index),
+            Err(e) => {
+                println!("{}", e);
+                None
             }
-            // 创建文件并写入空数组，避免后续解析错误
-            std::fs::write(&path, "[]")?;
         }
-        let json = std::fs::read_to_string(path)?;
-        let todos: Vec<Todo> = serde_json::from_str(&json)?;
-        self.todos = todos;
-        Ok(())
     }
 
     fn insert_todo(&mut self) {
@@ -137,13 +491,24 @@ impl Todos {
         println!("输入待办内容：");
         todo.content = self.read_user_input("读取内容失败");
         todo.create_time = chrono::Local::now().timestamp();
-        println!("输入时间限制（天）：");
-        let mut ddl = String::new();
-        std::io::stdin().read_line(&mut ddl).expect("读取时间限制失败");
-        let ddl: i32 = ddl.trim().parse().expect("请输入一个有效的数字");
-        todo.dead_line = chrono::Local::now().timestamp() + (ddl as i64) * 24 * 3600;
-        self.todos.push(todo);
-        let _ = self.save_todos();
+        println!("输入优先级（P0-P3，留空默认 P2）：");
+        let priority = self.read_user_input("读取优先级失败");
+        if !priority.trim().is_empty() {
+            match Priority::parse(&priority) {
+                Some(priority) => todo.priority = priority,
+                None => println!("无效的优先级，使用默认值 P2"),
+            }
+        }
+        println!("输入标签（逗号分隔，可留空）：");
+        todo.tags = parse_tags(&self.read_user_input("读取标签失败"));
+        println!("输入所属项目（可留空）：");
+        todo.project = parse_project(&self.read_user_input("读取项目失败"));
+        todo.dead_line = self.read_deadline(None);
+        todo.recurrence = self.read_recurrence(None);
+        todo.depends_on = self.read_dependencies(None);
+        let id = self.add_todo(todo);
+        println!("已添加待办事项，ID: {}", id);
+        self.persist();
     }
     
     // 辅助函数：读取用户输入并处理
@@ -153,103 +518,483 @@ impl Todos {
         input.trim_end().to_string()
     }
 
-        fn remove_todo(&mut self) {
-            println!("输入待办标题：");
-            let mut title = String::new();
-            match std::io::stdin().read_line(&mut title) {
-                Ok(_) => {
-                    title = title.trim_end().to_string();
-                    if title.is_empty() {
-                        println!("标题不能为空");
+    // 读取截止时间，输入无法识别时提示并重新输入；
+    // current 为 Some 时留空保持原值，否则留空表示无截止时间
+    fn read_deadline(&self, current: Option<Option<i64>>) -> Option<i64> {
+        loop {
+            match current {
+                Some(dead_line) => println!(
+                    "输入截止时间（当前: {}，留空保持不变，none 表示无截止时间，? 查看格式）：",
+                    format_deadline(dead_line)
+                ),
+                None => println!("输入截止时间（如 3d、2026-11-03 18:00、明天 9点，留空或 none 表示无截止时间，? 查看格式）："),
+            }
+            let input = self.read_user_input("读取截止时间失败");
+            match input.trim() {
+                "" => return current.flatten(),
+                "?" | "？" => {
+                    println!("{}", DEADLINE_HELP);
+                    continue;
+                },
+                _ => {},
+            }
+            match parse_deadline(&input, chrono::Local::now()) {
+                Ok(dead_line) => {
+                    println!("截止时间: {}", format_deadline(dead_line));
+                    return dead_line;
+                },
+                Err(e) => println!("{}，请重新输入", e),
+            }
+        }
+    }
+
+    // 读取重复规则，输入无法识别时重新输入；current 为 Some 时留空保持原值，否则留空表示不重复
+    fn read_recurrence(&self, current: Option<Option<Recurrence>>) -> Option<Recurrence> {
+        loop {
+            match &current {
+                Some(recurrence) => println!(
+                    "输入重复规则（当前: {}，留空保持不变，none 表示不重复，? 查看格式）：",
+                    recurrence.as_ref().map(|r| r.label()).unwrap_or_else(|| String::from("不重复"))
+                ),
+                None => println!("输入重复规则（如 daily、weekly:mon,fri、monthly:15、every:3，留空表示不重复，? 查看格式）："),
+            }
+            let input = self.read_user_input("读取重复规则失败");
+            match input.trim() {
+                "" => return current.flatten(),
+                "?" | "？" => {
+                    println!("{}", RECURRENCE_HELP);
+                    continue;
+                },
+                _ => {},
+            }
+            match Recurrence::parse(&input) {
+                Ok(recurrence) => return recurrence,
+                Err(e) => println!("{}，请重新输入", e),
+            }
+        }
+    }
+
+    // 读取依赖的事项，无效或形成循环时重新输入；index 为 Some 时留空保持原值，输入 - 清空
+    fn read_dependencies(&self, index: Option<usize>) -> Vec<String> {
+        loop {
+            match index {
+                Some(index) => {
+                    let current = &self.todos[index].depends_on;
+                    let current = if current.is_empty() { String::from("无") } else { current.join(",") };
+                    println!("输入依赖的事项 ID 或序号（逗号分隔，当前: {}，输入 - 清空，留空保持不变）：", current);
+                },
+                None => println!("输入依赖的事项 ID 或序号（逗号分隔，可留空）："),
+            }
+            let input = self.read_user_input("读取依赖失败");
+            match (input.trim(), index) {
+                ("", Some(index)) => return self.todos[index].depends_on.clone(),
+                ("-", _) => return Vec::new(),
+                _ => {},
+            }
+            match self.parse_dependencies(index, &input) {
+                Ok(ids) => return ids,
+                Err(e) => println!("{}，请重新输入", e),
+            }
+        }
+    }
+
+    fn change_dependencies(&mut self) {
+        let Some(index) = self.prompt_index() else {
+            return;
+        };
+        let depends_on = self.read_dependencies(Some(index));
+        if depends_on == self.todos[index].depends_on {
+            println!("未做任何修改");
+            return;
+        }
+        let todo = &mut self.todos[index];
+        todo.depends_on = depends_on;
+        todo.touch();
+        println!("已更新 '{}' 的依赖", todo.title);
+        self.persist();
+    }
+
+    fn remove_todo(&mut self) {
+        let Some(index) = self.prompt_index() else {
+            return;
+        };
+        let removed_todo = self.todos.remove(index);
+        println!("成功删除待办事项: {}", removed_todo.title);
+        self.persist();
+    }
+
+    // 逐项编辑，每项显示当前值，直接回车保持不变
+    fn edit_todo(&mut self) {
+        let Some(index) = self.prompt_index() else {
+            return;
+        };
+        let current = &self.todos[index];
+        let (old_title, old_content, old_priority, old_dead_line, old_recurrence) = (
+            current.title.clone(),
+            current.content.clone(),
+            current.priority,
+            current.dead_line,
+            current.recurrence.clone(),
+        );
+
+        println!("输入待办标题（当前: {}，留空保持不变）：", old_title);
+        let title = self.read_user_input("读取标题失败");
+        println!("输入待办内容（当前: {}，留空保持不变）：", old_content);
+        let content = self.read_user_input("读取内容失败");
+        let priority = loop {
+            println!("输入优先级（当前: {}，留空保持不变）：", old_priority.label());
+            let input = self.read_user_input("读取优先级失败");
+            if input.trim().is_empty() {
+                break old_priority;
+            }
+            match Priority::parse(&input) {
+                Some(priority) => break priority,
+                None => println!("无效的优先级: {}，请输入 P0-P3", input),
+            }
+        };
+        let dead_line = self.read_deadline(Some(old_dead_line));
+        let recurrence = self.read_recurrence(Some(old_recurrence.clone()));
+        let tags_changed = self.edit_tags_and_project(index);
+        let depends_on = self.read_dependencies(Some(index));
+
+        // 原地修改，保留 ID、状态和创建时间
+        let todo = &mut self.todos[index];
+        let mut changed = tags_changed;
+        if !title.trim().is_empty() && title.trim() != old_title {
+            todo.title = title.trim().to_string();
+            changed = true;
+        }
+        if !content.is_empty() && content != old_content {
+            todo.content = content;
+            changed = true;
+        }
+        if priority != old_priority {
+            todo.priority = priority;
+            changed = true;
+        }
+        if dead_line != old_dead_line {
+            todo.dead_line = dead_line;
+            changed = true;
+        }
+        if recurrence != old_recurrence {
+            todo.recurrence = recurrence;
+            changed = true;
+        }
+        if depends_on != todo.depends_on {
+            todo.depends_on = depends_on;
+            changed = true;
+        }
+        if !changed {
+            println!("未做任何修改");
+            return;
+        }
+        todo.touch();
+        println!("成功更新待办事项!");
+        self.persist();
+    }
+
+    fn change_status(&mut self, status: Option<Status>) {
+        let Some(index) = self.prompt_index() else {
+            return;
+        };
+        let status = match status {
+            Some(status) => status,
+            None => {
+                println!("输入新状态（pending/in-progress/done/cancelled）：");
+                let input = self.read_user_input("读取状态失败");
+                match Status::parse(&input) {
+                    Some(status) => status,
+                    None => {
+                        println!("无效的状态: {}", input);
                         return;
                     }
-                    let index = self.todos.iter().position(|t| t.title.trim() == title);
-                    if let Some(index) = index {
-                        let removed_todo = self.todos.remove(index);
-                        println!("成功删除待办事项: {}", removed_todo.title);
-                    } else {
-                        println!("未找到标题为 '{}' 的待办事项", title);
-                    }
-                    let _ = self.save_todos();
                 }
-                Err(error) => {
-                    println!("读取输入时发生错误: {}", error);
+            }
+        };
+        if status == Status::Done && self.todos[index].status != Status::Done {
+            let blockers = self.blockers(index);
+            if !blockers.is_empty() {
+                println!("警告：'{}' 依赖的事项尚未完成: {}", self.todos[index].title, depend::describe(&blockers));
+                println!("仍要标记为已完成吗？(y/N)");
+                let confirm = self.read_user_input("读取输入失败");
+                if !matches!(confirm.trim().to_lowercase().as_str(), "y" | "yes" | "是") {
+                    println!("已取消");
+                    return;
                 }
             }
         }
+        let next = self.set_status(index, status);
+        println!("已将 '{}' 标记为{}", self.todos[index].title, status.label());
+        if let Some(id) = next {
+            println!("已生成下一次重复事项，ID: {}", id);
+        }
+        self.persist();
+    }
 
-    fn edit_todo(&mut self) {
-        let mut todo = Todo::default();
-    
-        println!("输入待办标题：");
-        match std::io::stdin().read_line(&mut todo.title) {
-            Ok(_) => {
-                todo.title = todo.title.trim_end().to_string();
-                let index = self.todos.iter().position(|t| t.title.eq(&todo.title));
-                match index {
-                    Some(index) => {
-                        println!("输入待办内容：");
-                        match std::io::stdin().read_line(&mut todo.content) {
-                            Ok(_) => {
-                                todo.content = todo.content.trim_end().to_string();
-                                println!("输入时间限制（天）：");
-                                let mut ddl = String::new();
-                                match std::io::stdin().read_line(&mut ddl) {
-                                    Ok(_) => {
-                                        // 验证时间输入是否为有效数字
-                                        match ddl.trim().parse::<u32>() {
-                                            Ok(days) => {
-                                                todo.create_time = chrono::Local::now().timestamp();
-                                                todo.dead_line = chrono::Local::now().timestamp() + (days as i64) * 24 * 3600;
-                                                // 直接替换而不是先删后加，保持顺序
-                                                self.todos[index] = todo;
-                                                println!("成功更新待办事项!");
-                                            },
-                                            Err(_) => {
-                                                println!("时间限制必须为有效数字");
-                                            }
-                                        }
-                                    },
-                                    Err(error) => {
-                                        println!("读取时间限制失败: {}", error);
-                                    }
-                                }
-                            },
-                            Err(error) => {
-                                println!("读取内容失败: {}", error);
-                            }
-                        }
-                    },
-                    None => {
-                        println!("请输入正确的标题")
-                    }
+    fn change_priority(&mut self) {
+        let Some(index) = self.prompt_index() else {
+            return;
+        };
+        println!("输入优先级（P0-P3）：");
+        let input = self.read_user_input("读取优先级失败");
+        let Some(priority) = Priority::parse(&input) else {
+            println!("无效的优先级: {}", input);
+            return;
+        };
+        self.todos[index].priority = priority;
+        self.todos[index].touch();
+        println!("已将 '{}' 的优先级设为 {}", self.todos[index].title, priority.label());
+        self.persist();
+    }
+
+    fn change_tags(&mut self) {
+        let Some(index) = self.prompt_index() else {
+            return;
+        };
+        if self.edit_tags_and_project(index) {
+            self.todos[index].touch();
+            println!("已更新 '{}' 的标签和项目", self.todos[index].title);
+            self.persist();
+        } else {
+            println!("未做任何修改");
+        }
+    }
+
+    // 对单个事项的检查项进行添加、勾选、取消勾选、删除和自动完成设置
+    fn manage_subtasks(&mut self) {
+        let Some(index) = self.prompt_index() else {
+            return;
+        };
+        let todo = &self.todos[index];
+        match todo.progress() {
+            Some((done, total)) => {
+                println!("'{}' 的检查项 [{}/{}]:", todo.title, done, total);
+                todo.show_subtasks();
+            },
+            None => println!("'{}' 还没有检查项", todo.title),
+        }
+        println!("输入操作（add <内容> / tick <序号> / untick <序号> / rm <序号> / auto on|off）：");
+        let input = self.read_user_input("读取操作失败");
+        let (action, arg) = match input.trim().split_once(char::is_whitespace) {
+            Some((action, arg)) => (action.to_lowercase(), arg.trim()),
+            None => (input.trim().to_lowercase(), ""),
+        };
+        let number = || arg.parse::<usize>().map_err(|_| format!("无效的检查项序号: {}", arg));
+        let result = match action.as_str() {
+            "add" => self.add_subtask(index, arg).map(|_| String::from("已添加检查项")),
+            "tick" | "done" => number().and_then(|n| self.tick_subtask(index, n, true)).map(|outcome| {
+                let mut message = String::from("已勾选检查项");
+                if outcome.blocked {
+                    message.push_str("，检查项已全部完成，但仍有未完成的依赖，未自动完成");
+                }
+                if outcome.parent_completed {
+                    message.push_str(&format!("，检查项已全部完成，'{}' 已标记为已完成", self.todos[index].title));
                 }
+                if let Some(id) = outcome.next_id {
+                    message.push_str(&format!("\n已生成下一次重复事项，ID: {}", id));
+                }
+                message
+            }),
+            "untick" | "undone" => number().and_then(|n| self.tick_subtask(index, n, false))
+                .map(|_| String::from("已取消勾选检查项")),
+            "rm" | "remove" => number().and_then(|n| self.remove_subtask(index, n))
+                .map(|s| format!("已删除检查项: {}", s.text)),
+            "auto" => match arg.to_lowercase().as_str() {
+                "on" | "yes" | "开" => Ok(true),
+                "off" | "no" | "关" => Ok(false),
+                _ => Err(format!("无效的设置: {}，请输入 on 或 off", arg)),
+            }.map(|auto| {
+                self.todos[index].auto_complete = auto;
+                self.todos[index].touch();
+                String::from(if auto { "已开启自动完成" } else { "已关闭自动完成" })
+            }),
+            "" => return,
+            _ => Err(format!("无效的操作: {}", action)),
+        };
+        match result {
+            Ok(message) => {
+                println!("{}", message);
+                self.persist();
             },
-            Err(error) => {
-                println!("读取标题失败: {}", error);
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    // 按当前的显示选项导出，文件名留空时直接输出
+    fn export_todos(&self, opts: &ListOptions) {
+        println!("输入导出格式（csv/md/todotxt/ics）：");
+        let input = self.read_user_input("读取导出格式失败");
+        let Some(mut format) = ExportFormat::parse(&input) else {
+            println!("无效的导出格式: {}", input);
+            return;
+        };
+        if let ExportFormat::Ics { events } = &mut format {
+            println!("是否为截止时间生成日历事件？(y/N)");
+            let confirm = self.read_user_input("读取输入失败");
+            *events = matches!(confirm.trim().to_lowercase().as_str(), "y" | "yes" | "是");
+        }
+        println!("输入导出文件路径（留空直接显示）：");
+        let path = self.read_user_input("读取文件路径失败");
+        let entries = opts.select(&self.todos);
+        let content = match format.render(&entries) {
+            Ok(content) => content,
+            Err(e) => {
+                println!("导出失败: {}", e);
+                return;
             }
+        };
+        if path.trim().is_empty() {
+            println!("{}", content);
+            return;
+        }
+        match std::fs::write(path.trim(), content) {
+            Ok(()) => println!("已导出 {} 条待办事项到 {}", entries.len(), path.trim()),
+            Err(e) => println!("写入 {} 失败: {}", path.trim(), e),
         }
     }
 
-    fn show_todos(&self) {
-        if self.todos.is_empty() {
+    // 从 CSV/todo.txt/JSON 文件导入，有无效行时询问是否跳过
+    fn import_todos(&mut self) {
+        println!("输入要导入的文件路径：");
+        let path = self.read_user_input("读取文件路径失败");
+        let path = path.trim();
+        let format = match ImportFormat::from_path(path) {
+            Some(format) => format,
+            None => {
+                println!("输入文件格式（csv/todotxt/json/ics）：");
+                let input = self.read_user_input("读取文件格式失败");
+                let Some(format) = ImportFormat::parse(&input) else {
+                    println!("无效的文件格式: {}", input);
+                    return;
+                };
+                format
+            }
+        };
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                println!("读取 {} 失败: {}", path, e);
+                return;
+            }
+        };
+        let (items, errors) = import::parse(format, &text, &Default::default());
+        for error in &errors {
+            println!("第 {} 行: {}", error.line, error.message);
+        }
+        if !errors.is_empty() {
+            println!("共 {} 处错误，是否跳过这些行继续导入？(y/N)", errors.len());
+            let confirm = self.read_user_input("读取输入失败");
+            if !matches!(confirm.trim().to_lowercase().as_str(), "y" | "yes" | "是") {
+                println!("已取消导入");
+                return;
+            }
+        }
+        let report = self.import(items);
+        for (line, title) in &report.duplicates {
+            println!("第 {} 行已存在，跳过: {}", line, title);
+        }
+        for warning in &report.warnings {
+            println!("{}", warning);
+        }
+        println!("已导入 {} 条，跳过重复 {} 条，无效 {} 条", report.added, report.duplicates.len(), errors.len());
+        if report.added > 0 {
+            self.persist();
+        }
+    }
+
+    // 读取并应用标签和项目，返回是否有改动（不负责保存）
+    fn edit_tags_and_project(&mut self, index: usize) -> bool {
+        let todo = &self.todos[index];
+        let current_tags = if todo.tags.is_empty() { String::from("无") } else { todo.tags.join(",") };
+        println!("输入标签（逗号分隔，当前: {}，输入 - 清空，留空保持不变）：", current_tags);
+        let tags = self.read_user_input("读取标签失败");
+        println!("输入所属项目（当前: {}，输入 - 清空，留空保持不变）：", todo.project.as_deref().unwrap_or("无"));
+        let project = self.read_user_input("读取项目失败");
+        let todo = &mut self.todos[index];
+        let (old_tags, old_project) = (todo.tags.clone(), todo.project.clone());
+        match tags.trim() {
+            "" => {},
+            "-" => todo.tags.clear(),
+            tags => todo.tags = parse_tags(tags),
+        }
+        match project.trim() {
+            "" => {},
+            "-" => todo.project = None,
+            project => todo.project = parse_project(project),
+        }
+        todo.tags != old_tags || todo.project != old_project
+    }
+
+    /// 显示待办事项，序号始终为其在列表中的位置，排序后仍可用于定位
+    pub fn show_todos(&self, opts: &ListOptions) {
+        let visible = opts.select(&self.todos);
+        let hidden = self.todos.len() - visible.len();
+        if !visible.is_empty() {
+            println!("{}", DueSummary::collect(&visible, chrono::Local::now()).render());
+        }
+        // 被阻塞的事项单独列在最后
+        let (visible, blocked): (Vec<_>, Vec<_>) = visible.into_iter()
+            .partition(|(_, t)| !depend::is_blocked(&self.todos, t));
+        if visible.is_empty() && blocked.is_empty() {
             println!("暂无待办事项");
+        } else if visible.is_empty() {
+            println!("暂无可以开始的事项");
+        } else if opts.group_by_project {
+            println!("待办事项列表（按项目分组）:");
+            for (project, items) in view::group_by_project(visible) {
+                println!("════════════════════════════════════════════════════════════════════════");
+                println!("📁 {} ({})", project.unwrap_or("未归属项目"), items.len());
+                println!("════════════════════════════════════════════════════════════════════════");
+                for (i, t) in items {
+                    println!("{}. ", i + 1);
+                    t.show();
+                }
+            }
         } else {
             println!("待办事项列表:");
             println!("════════════════════════════════════════════════════════════════════════");
-            for (i, t) in self.todos.iter().enumerate() {
+            for (i, t) in visible {
                 println!("{}. ", i + 1);
                 t.show();
             }
         }
+        if !blocked.is_empty() {
+            println!("════════════════════════════════════════════════════════════════════════");
+            println!("⛔ 被阻塞的事项 ({})", blocked.len());
+            println!("════════════════════════════════════════════════════════════════════════");
+            for (i, t) in blocked {
+                println!("{}. 等待: {}", i + 1, depend::describe(&depend::open_dependencies(&self.todos, t)));
+                t.show();
+            }
+        }
+        if hidden > 0 {
+            println!("（已隐藏 {} 条已完成/已取消或不符合筛选条件的事项）", hidden);
+        }
+    }
+}
+
+// 关键字与筛选表达式都为空时清除筛选条件
+fn build_filter(keyword: &str, expr: &str) -> Result<Option<Filter>, String> {
+    let mut filter = Filter::parse(expr)?;
+    if !keyword.is_empty() {
+        let search = match keyword.strip_prefix('/') {
+            Some(pattern) => Filter::search(pattern, true)?,
+            None => Filter::search(keyword, false)?,
+        };
+        filter = search.and(filter);
     }
+    Ok(if filter.is_empty() { None } else { Some(filter) })
 }
 
 pub fn todo_run() {
     let mut todos = Todos::default();
-    let _ = todos.load_todos();
+    if let Err(e) = todos.load_todos()
+        && !todos.recover(&e) {
+        return;
+    }
+    let mut opts = ListOptions::default();
     loop {
-        todos.show_todos();
+        todos.show_todos(&opts);
         println!("\n╔════════════════════════════════════════════════════════════════════════╗");
         println!("║                              操作菜单                                  ║");
         println!("╠════════════════════════════════════════════════════════════════════════╣");
@@ -259,6 +1004,20 @@ pub fn todo_run() {
         println!("║   2    │ 编辑待办事项 (edit)                                           ║");
         println!("║   3    │ 删除待办事项 (remove/delete)                                  ║");
         println!("║   4    │ 显示待办事项 (show/list)                                      ║");
+        println!("║   5    │ 标记为已完成 (done)                                           ║");
+        println!("║   6    │ 更改状态 (status)                                             ║");
+        println!("║   7    │ 显示/隐藏已完成事项 (all)                                     ║");
+        println!("║   8    │ 设置优先级 (priority)                                         ║");
+        println!("║   9    │ 排序方式 (sort)                                               ║");
+        println!("║  10    │ 设置标签和项目 (tag/project)                                  ║");
+        println!("║  11    │ 按标签/项目筛选 (filter)                                      ║");
+        println!("║  12    │ 按项目分组显示 (group)                                        ║");
+        println!("║  13    │ 搜索/筛选表达式 (search)                                      ║");
+        println!("║  14    │ 管理检查项 (sub)                                              ║");
+        println!("║  15    │ 设置依赖 (depend)                                             ║");
+        println!("║  16    │ 只看可以开始的事项/显示全部 (ready)                           ║");
+        println!("║  17    │ 导出当前列表 (export)                                         ║");
+        println!("║  18    │ 从文件导入 (import)                                           ║");
         println!("║   q    │ 退出                                                          ║");
         println!("╚════════════════════════════════════════════════════════════════════════╝");
         println!("请选择操作:");
@@ -269,7 +1028,11 @@ pub fn todo_run() {
             println!("感谢使用待办事项列表！");
             break;
         }
-        match Handler::analyse_flag(&flag) {
+        let handler = Handler::analyse_flag(&flag);
+        if handler.modifies() && !todos.begin_edit() {
+            continue;
+        }
+        match handler {
             Handler::INSERT => {
                 todos.insert_todo();
             },
@@ -280,7 +1043,73 @@ pub fn todo_run() {
             },
             Handler::REMOVE => {
                 todos.remove_todo();
+            },
+            Handler::DONE => {
+                todos.change_status(Some(Status::Done));
+            },
+            Handler::STATUS => {
+                todos.change_status(None);
+            },
+            Handler::ALL => {
+                opts.show_all = !opts.show_all;
+            },
+            Handler::PRIORITY => {
+                todos.change_priority();
+            },
+            Handler::SORT => {
+                println!("输入排序方式（priority/due/created/title，加 :desc 降序，留空恢复默认顺序）：");
+                let input = todos.read_user_input("读取排序方式失败");
+                if input.trim().is_empty() {
+                    opts.sort = None;
+                } else {
+                    match Sort::parse(&input) {
+                        Some(sort) => {
+                            println!("已按{}排序", sort.label());
+                            opts.sort = Some(sort);
+                        },
+                        None => println!("无效的排序方式: {}", input),
+                    }
+                }
+            },
+            Handler::TAG => {
+                todos.change_tags();
+            },
+            Handler::FILTER => {
+                println!("输入要筛选的标签（逗号分隔，留空不限）：");
+                opts.tags = parse_tags(&todos.read_user_input("读取标签失败"));
+                println!("输入要筛选的项目（留空不限）：");
+                opts.project = parse_project(&todos.read_user_input("读取项目失败"));
+            },
+            Handler::GROUP => {
+                opts.group_by_project = !opts.group_by_project;
+            },
+            Handler::SEARCH => {
+                println!("输入搜索关键字（匹配标题和内容，以 / 开头表示正则表达式，留空不限）：");
+                let keyword = todos.read_user_input("读取关键字失败");
+                println!("{}", FILTER_HELP);
+                println!("输入筛选表达式（留空不限）：");
+                let expr = todos.read_user_input("读取筛选表达式失败");
+                match build_filter(keyword.trim(), expr.trim()) {
+                    Ok(filter) => opts.filter = filter,
+                    Err(e) => println!("{}", e),
+                }
+            },
+            Handler::SUBTASK => {
+                todos.manage_subtasks();
+            },
+            Handler::DEPEND => {
+                todos.change_dependencies();
+            },
+            Handler::READY => {
+                opts.ready_only = !opts.ready_only;
+            },
+            Handler::EXPORT => {
+                todos.export_todos(&opts);
+            },
+            Handler::IMPORT => {
+                todos.import_todos();
             }
         }
+        todos.end_edit();
     }
 }
\ No newline at end of file
@@ -0,0 +1,364 @@
+use std::cmp::Ordering;
+use std::io::IsTerminal;
+
+use chrono::{DateTime, Datelike, Duration, Local};
+
+use crate::todo_mod::depend;
+use crate::todo_mod::filter::Filter;
+use crate::todo_mod::todo_list::Todo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Priority,
+    Deadline,
+    Created,
+    Title,
+}
+
+/// 列表排序方式，如 `priority`、`due:desc`、`-created`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl Sort {
+    pub fn parse(input: &str) -> Option<Sort> {
+        let input = input.trim().to_lowercase();
+        let (name, descending) = if let Some(name) = input.strip_prefix('-') {
+            (name.to_string(), true)
+        } else if let Some((name, order)) = input.split_once(':') {
+            match order {
+                "asc" => (name.to_string(), false),
+                "desc" => (name.to_string(), true),
+                _ => return None,
+            }
+        } else {
+            (input, false)
+        };
+        let key = match name.as_str() {
+            "priority" | "pri" | "p" => SortKey::Priority,
+            "due" | "deadline" | "dead_line" | "ddl" => SortKey::Deadline,
+            "created" | "create_time" | "ctime" => SortKey::Created,
+            "title" | "name" => SortKey::Title,
+            _ => return None,
+        };
+        Some(Sort { key, descending })
+    }
+
+    pub fn label(&self) -> String {
+        let key = match self.key {
+            SortKey::Priority => "优先级",
+            SortKey::Deadline => "截止时间",
+            SortKey::Created => "创建时间",
+            SortKey::Title => "标题",
+        };
+        format!("{}（{}）", key, if self.descending { "降序" } else { "升序" })
+    }
+
+    // 优先级相同时按截止时间排，截止时间相同时按优先级排，让紧急且重要的事项靠前
+    fn compare(&self, a: &Todo, b: &Todo) -> Ordering {
+        let ordering = match self.key {
+            SortKey::Priority => a.priority.cmp(&b.priority)
+                .then(deadline_key(a).cmp(&deadline_key(b))),
+            SortKey::Deadline => deadline_key(a).cmp(&deadline_key(b))
+                .then(a.priority.cmp(&b.priority)),
+            SortKey::Created => a.create_time.cmp(&b.create_time),
+            SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        };
+        if self.descending { ordering.reverse() } else { ordering }
+    }
+}
+
+// 没有截止时间的事项在升序时排在最后
+fn deadline_key(todo: &Todo) -> i64 {
+    todo.dead_line.unwrap_or(i64::MAX)
+}
+
+/// 待显示的事项及其在原列表中的位置
+pub type Entry<'a> = (usize, &'a Todo);
+
+/// 列表显示选项，交互菜单和命令行共用
+#[derive(Debug, Default, Clone)]
+pub struct ListOptions {
+    /// 是否显示已完成/已取消的事项
+    pub show_all: bool,
+    /// 为 None 时保持插入顺序
+    pub sort: Option<Sort>,
+    /// 只显示同时带有这些标签的事项
+    pub tags: Vec<String>,
+    /// 只显示属于该项目的事项
+    pub project: Option<String>,
+    /// 按项目分组显示
+    pub group_by_project: bool,
+    /// 搜索关键字或筛选表达式
+    pub filter: Option<Filter>,
+    /// 只显示可以立即开始的事项，即未关闭且没有未完成依赖的事项
+    pub ready_only: bool,
+}
+
+impl ListOptions {
+    /// 返回需要显示的事项及其在原列表中的位置
+    pub fn select<'a>(&self, todos: &'a [Todo]) -> Vec<Entry<'a>> {
+        let mut visible: Vec<Entry> = todos.iter()
+            .enumerate()
+            .filter(|(_, t)| self.show_all || self.filter_mentions_status() || !t.status.is_closed())
+            .filter(|(_, t)| self.tags.iter().all(|tag| t.has_tag(tag)))
+            .filter(|(_, t)| match &self.project {
+                Some(project) => t.project.as_deref()
+                    .is_some_and(|p| p.eq_ignore_ascii_case(project)),
+                None => true,
+            })
+            .filter(|(_, t)| self.filter.as_ref().is_none_or(|f| f.matches(t)))
+            .filter(|(_, t)| !self.ready_only || (!t.status.is_closed() && !depend::is_blocked(todos, t)))
+            .collect();
+        if let Some(sort) = self.sort {
+            visible.sort_by(|(_, a), (_, b)| sort.compare(a, b));
+        }
+        visible
+    }
+
+    fn filter_mentions_status(&self) -> bool {
+        self.filter.as_ref().is_some_and(|f| f.mentions_status())
+    }
+}
+
+/// 按项目名称分组，保持组内顺序不变，未归属项目的事项放在最后
+pub fn group_by_project<'a>(visible: Vec<Entry<'a>>) -> Vec<(Option<&'a str>, Vec<Entry<'a>>)> {
+    let mut groups: Vec<(Option<&str>, Vec<Entry>)> = Vec::new();
+    for (i, t) in visible {
+        let project = t.project.as_deref();
+        match groups.iter_mut().find(|(p, _)| *p == project) {
+            Some((_, items)) => items.push((i, t)),
+            None => groups.push((project, vec![(i, t)])),
+        }
+    }
+    groups.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+    groups
+}
+
+/// 距截止时间不足该秒数时视为即将到期
+const DUE_SOON_SECS: i64 = 24 * 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Overdue,
+    DueSoon,
+    Normal,
+}
+
+/// 已关闭或没有截止时间的事项不标记紧急程度
+pub fn urgency(todo: &Todo, now: i64) -> Urgency {
+    match todo.dead_line {
+        Some(_) if todo.status.is_closed() => Urgency::Normal,
+        Some(d) if d < now => Urgency::Overdue,
+        Some(d) if d - now <= DUE_SOON_SECS => Urgency::DueSoon,
+        _ => Urgency::Normal,
+    }
+}
+
+/// 标准输出为终端且未设置 NO_COLOR 时，逾期显示为红色，即将到期显示为黄色
+pub fn paint(text: &str, urgency: Urgency) -> String {
+    let color = match urgency {
+        Urgency::Overdue => "31",
+        Urgency::DueSoon => "33",
+        Urgency::Normal => return text.to_string(),
+    };
+    if std::env::var_os("NO_COLOR").is_some() || !std::io::stdout().is_terminal() {
+        return text.to_string();
+    }
+    format!("\x1B[{}m{}\x1B[0m", color, text)
+}
+
+/// 未关闭事项的到期统计，“本周”截止到本周日结束，包含今天
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DueSummary {
+    pub overdue: usize,
+    pub today: usize,
+    pub this_week: usize,
+}
+
+impl DueSummary {
+    pub fn collect(entries: &[Entry], now: DateTime<Local>) -> DueSummary {
+        let today = now.date_naive();
+        let end_of_week = today + Duration::days(6 - today.weekday().num_days_from_monday() as i64);
+        let mut summary = DueSummary::default();
+        for (_, t) in entries {
+            let Some(d) = t.dead_line.filter(|_| !t.status.is_closed()) else {
+                continue;
+            };
+            let Some(date) = DateTime::from_timestamp(d, 0).map(|dt| dt.with_timezone(&Local).date_naive()) else {
+                continue;
+            };
+            if d < now.timestamp() {
+                summary.overdue += 1;
+                continue;
+            }
+            if date == today {
+                summary.today += 1;
+            }
+            if date <= end_of_week {
+                summary.this_week += 1;
+            }
+        }
+        summary
+    }
+
+    pub fn render(&self) -> String {
+        let overdue = format!("已逾期 {} 项", self.overdue);
+        let today = format!("今天到期 {} 项", self.today);
+        format!(
+            "{} · {} · 本周到期 {} 项",
+            if self.overdue > 0 { paint(&overdue, Urgency::Overdue) } else { overdue },
+            if self.today > 0 { paint(&today, Urgency::DueSoon) } else { today },
+            self.this_week
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::todo_mod::todo_list::{Priority, Status};
+
+    fn todo(id: &str, priority: Priority, dead_line: Option<i64>) -> Todo {
+        Todo { id: id.to_string(), title: id.to_string(), priority, dead_line, ..Default::default() }
+    }
+
+    fn ids(entries: &[Entry]) -> Vec<String> {
+        entries.iter().map(|(_, t)| t.id.clone()).collect()
+    }
+
+    fn sorted(todos: &[Todo], sort: &str) -> Vec<String> {
+        let opts = ListOptions { sort: Sort::parse(sort), ..Default::default() };
+        ids(&opts.select(todos))
+    }
+
+    #[test]
+    fn parses_sort_orders() {
+        assert_eq!(Sort::parse("due"), Some(Sort { key: SortKey::Deadline, descending: false }));
+        assert_eq!(Sort::parse(" Priority:DESC "), Some(Sort { key: SortKey::Priority, descending: true }));
+        assert_eq!(Sort::parse("-created"), Some(Sort { key: SortKey::Created, descending: true }));
+        assert_eq!(Sort::parse("title:asc"), Some(Sort { key: SortKey::Title, descending: false }));
+        assert_eq!(Sort::parse("due:up"), None);
+        assert_eq!(Sort::parse("size"), None);
+    }
+
+    #[test]
+    fn sorts_with_tie_breakers_and_keeps_order_of_equal_items() {
+        let todos = [
+            todo("a", Priority::P2, None),
+            todo("b", Priority::P0, Some(300)),
+            todo("c", Priority::P2, Some(100)),
+            todo("d", Priority::P0, Some(100)),
+            todo("e", Priority::P2, None),
+        ];
+        // 优先级相同时按截止时间，没有截止时间的排在最后，其余保持原有顺序
+        assert_eq!(sorted(&todos, "priority"), ["d", "b", "c", "a", "e"]);
+        assert_eq!(sorted(&todos, "due"), ["d", "c", "b", "a", "e"]);
+        assert_eq!(sorted(&todos, "due:desc"), ["a", "e", "b", "c", "d"]);
+        assert_eq!(sorted(&todos, "-priority"), ["a", "e", "c", "b", "d"]);
+
+        let mut todos = todos.to_vec();
+        for (i, t) in todos.iter_mut().enumerate() {
+            t.create_time = [5, 1, 5, 2, 5][i];
+            t.title = ["b", "A", "a", "c", "B"][i].to_string();
+        }
+        assert_eq!(sorted(&todos, "created"), ["b", "d", "a", "c", "e"]);
+        // 标题比较不区分大小写，相同的保持原有顺序
+        assert_eq!(sorted(&todos, "title"), ["b", "c", "a", "e", "d"]);
+        // 未指定排序时保持插入顺序
+        assert_eq!(ids(&ListOptions::default().select(&todos)), ["a", "b", "c", "d", "e"]);
+    }
+
+    #[test]
+    fn selects_by_status_tags_project_filter_and_readiness() {
+        let todos = [
+            Todo { tags: vec![String::from("bug"), String::from("ui")], project: Some(String::from("App")), ..todo("a", Priority::P2, None) },
+            Todo { status: Status::Done, tags: vec![String::from("bug")], ..todo("b", Priority::P2, None) },
+            Todo { depends_on: vec![String::from("a")], project: Some(String::from("app")), ..todo("c", Priority::P2, None) },
+            Todo { status: Status::Cancelled, ..todo("d", Priority::P1, None) },
+        ];
+        let select = |opts: ListOptions| ids(&opts.select(&todos));
+        assert_eq!(select(ListOptions::default()), ["a", "c"]);
+        assert_eq!(select(ListOptions { show_all: true, ..Default::default() }), ["a", "b", "c", "d"]);
+        assert_eq!(select(ListOptions { show_all: true, tags: vec![String::from("#BUG")], ..Default::default() }), ["a", "b"]);
+        assert_eq!(select(ListOptions { tags: vec![String::from("bug"), String::from("ui")], ..Default::default() }), ["a"]);
+        assert_eq!(select(ListOptions { project: Some(String::from("APP")), ..Default::default() }), ["a", "c"]);
+        assert_eq!(select(ListOptions { ready_only: true, show_all: true, ..Default::default() }), ["a"]);
+        // 筛选条件涉及状态时不再隐藏已关闭的事项
+        let filter = Filter::parse("status:closed").unwrap();
+        assert_eq!(select(ListOptions { filter: Some(filter), ..Default::default() }), ["b", "d"]);
+    }
+
+    #[test]
+    fn groups_by_project_with_unassigned_last() {
+        let todos = [
+            todo("a", Priority::P2, None),
+            Todo { project: Some(String::from("web")), ..todo("b", Priority::P2, None) },
+            Todo { project: Some(String::from("Api")), ..todo("c", Priority::P2, None) },
+            todo("d", Priority::P2, None),
+            Todo { project: Some(String::from("web")), ..todo("e", Priority::P2, None) },
+        ];
+        let opts = ListOptions { sort: Sort::parse("-title"), ..Default::default() };
+        let groups: Vec<(Option<&str>, Vec<String>)> = group_by_project(opts.select(&todos)).into_iter()
+            .map(|(project, items)| (project, ids(&items)))
+            .collect();
+        assert_eq!(groups, [
+            (Some("Api"), vec![String::from("c")]),
+            (Some("web"), vec![String::from("e"), String::from("b")]),
+            (None, vec![String::from("d"), String::from("a")]),
+        ]);
+        assert!(group_by_project(Vec::new()).is_empty());
+    }
+
+    fn at(d: u32, h: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 10, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn urgency_of_open_todos_with_deadline() {
+        let now = at(14, 12, 0).timestamp();
+        let due = |d: DateTime<Local>| todo("t", Priority::P2, Some(d.timestamp()));
+        assert_eq!(urgency(&due(at(14, 11, 59)), now), Urgency::Overdue);
+        assert_eq!(urgency(&due(at(14, 12, 0)), now), Urgency::DueSoon);
+        assert_eq!(urgency(&due(at(15, 12, 0)), now), Urgency::DueSoon);
+        assert_eq!(urgency(&due(at(15, 12, 1)), now), Urgency::Normal);
+        assert_eq!(urgency(&todo("t", Priority::P2, None), now), Urgency::Normal);
+        let done = Todo { status: Status::Done, ..due(at(1, 9, 0)) };
+        assert_eq!(urgency(&done, now), Urgency::Normal);
+        assert_eq!(paint("文本", Urgency::Normal), "文本");
+    }
+
+    #[test]
+    fn due_summary_buckets() {
+        // 2026-10-14 是周三，本周截止到 10-18 周日
+        let now = at(14, 12, 0);
+        let due = |id: &str, d: DateTime<Local>| todo(id, Priority::P2, Some(d.timestamp()));
+        let todos = [
+            due("yesterday", at(13, 9, 0)),
+            due("this morning", at(14, 11, 0)),
+            due("tonight", at(14, 23, 59)),
+            due("tomorrow", at(15, 9, 0)),
+            due("sunday", at(18, 23, 59)),
+            due("next monday", at(19, 0, 0)),
+            Todo { status: Status::Done, ..due("closed", at(13, 9, 0)) },
+            Todo { status: Status::Cancelled, ..due("cancelled", at(14, 18, 0)) },
+            todo("no deadline", Priority::P2, None),
+        ];
+        let entries: Vec<Entry> = todos.iter().enumerate().collect();
+        let summary = DueSummary::collect(&entries, now);
+        assert_eq!((summary.overdue, summary.today, summary.this_week), (2, 1, 3));
+
+        // 周日时本周只剩当天
+        let summary = DueSummary::collect(&entries, at(18, 8, 0));
+        assert_eq!((summary.overdue, summary.today, summary.this_week), (4, 1, 1));
+        assert_eq!(DueSummary::default().render(), "已逾期 0 项 · 今天到期 0 项 · 本周到期 0 项");
+    }
+}